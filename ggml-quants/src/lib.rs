@@ -84,7 +84,7 @@ where
     T: Send + Sync,
 {
     fn quantize_slice(dst: &mut [Self], src: &[T]) -> Result<(), QuantizeError> {
-        if src.len() % N != 0 {
+        if !src.len().is_multiple_of(N) {
             return Err(QuantizeError::Indivisible);
         }
         if dst.len() != src.len() / N {
@@ -98,7 +98,7 @@ where
     }
 
     fn dequantize_slice(dst: &mut [T], src: &[Self]) -> Result<(), QuantizeError> {
-        if dst.len() % N != 0 {
+        if !dst.len().is_multiple_of(N) {
             return Err(QuantizeError::Indivisible);
         }
         if src.len() != dst.len() / N {
@@ -150,6 +150,29 @@ pub(crate) mod test_utils {
         assert!(ec.outliers().is_empty());
     }
 
+    /// 以 llama.cpp `test-quantize-fns` 的方式，用 `0.1 + 2cos(i)` 生成 4096 个数，
+    /// 检查量化再反量化的总误差不超过 `max`。
+    pub fn test_total_error<const N: usize, T: Quantize<f32, N>>(max: f32) {
+        use std::iter::zip;
+
+        const LEN: usize = 4096;
+        let data: [f32; LEN] = std::array::from_fn(|i| 0.1 + 2. * (i as f32).cos());
+
+        let mut sum = 0f64;
+        for x in data.chunks_exact(N) {
+            let x = x.try_into().unwrap();
+            let y = T::dequantize(&T::quantize(x));
+            for (a, b) in zip(x, y) {
+                let diff = (a - b) as f64;
+                sum += diff * diff
+            }
+        }
+        let error = sum.sqrt() as f32 / LEN as f32;
+        println!("total error: {error:.3e}");
+
+        assert!(error < max)
+    }
+
     struct Diff {
         pub abs: f32,
         pub rel: f32,
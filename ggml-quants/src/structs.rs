@@ -1,6 +1,7 @@
 ﻿mod half;
 mod iq1m;
 mod iq1s;
+mod iq2;
 mod iq2s;
 mod iq2xs;
 mod iq2xxs;
@@ -8,6 +9,7 @@ mod iq3s;
 mod iq3xxs;
 mod iq4nl;
 mod iq4xs;
+mod k_quants;
 mod q2_k;
 mod q3_k;
 mod q4_0;
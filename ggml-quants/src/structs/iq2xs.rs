@@ -1,11 +1,17 @@
-﻿use super::{f16, _256};
+﻿use super::{
+    f16,
+    iq2::{dequantize_grid, quantize_iq2, Codebook, IQ2XS_GRID},
+    k_quants::nearest_int,
+    _256, _32,
+};
 use crate::{DataBlock, Quantize};
+use std::sync::LazyLock;
 
 #[repr(C)]
 pub struct IQ2XS {
     delta: f16,
     qs: [u16; _256 / 8],
-    qh: [u8; _256 / 32],
+    scales: [u8; _256 / 32],
 }
 
 impl_data_block! {
@@ -13,15 +19,65 @@ impl_data_block! {
     Self {
         delta: f16::ZERO,
         qs: [0; _256 / 8],
-        qh: [0; _256 / 32],
+        scales: [0; _256 / 32],
     }
 }
 
+static CODEBOOK: LazyLock<Codebook> = LazyLock::new(|| Codebook::new(&IQ2XS_GRID));
+
 impl Quantize<f32, _256> for IQ2XS {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    /// 没有重要性矩阵时以 `sqrt(sigma2 + x²)` 为权重，结果与 ggml 不逐位一致。
+    fn quantize(data: &[f32; _256]) -> Self {
+        let sigma2 = data.iter().map(|x| x * x).sum::<f32>() / _256 as f32;
+        // 每 8 个数占 1 个 u16，低 9 位为格点序号，高 7 位为符号；每 16 个数一个 4 位 scale
+        let mut qs = [0; _256 / 8];
+        let mut scales = [0f32; _256 / 16];
+        for (ib, xb) in data.chunks_exact(16).enumerate() {
+            let mut index = [0; 2];
+            let mut signs = [0; 2];
+            scales[ib] = quantize_iq2(&CODEBOOK, xb, sigma2, &mut index, &mut signs);
+            for k in 0..2 {
+                qs[2 * ib + k] = index[k] | (signs[k] as u16) << 9
+            }
+        }
+
+        let max_scale = scales.iter().fold(0f32, |max, &s| max.max(s));
+        if max_scale == 0. {
+            return Self::ZEROS;
+        }
+        let d = max_scale / 31.;
+        let id = d.recip();
+        let mut packed = [0; _256 / 32];
+        for (ib, &scale) in scales.iter().enumerate() {
+            let l = nearest_int(0.5 * (id * scale - 1.)).clamp(0, 15) as u8;
+            packed[ib / 2] |= l << (4 * (ib % 2))
+        }
+        Self {
+            delta: f16::from_f32(d),
+            qs,
+            scales: packed,
+        }
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let delta = self.delta.to_f32();
+        let mut ans = [0.; _256];
+        for (ib, y) in ans.chunks_exact_mut(_32).enumerate() {
+            let sc = self.scales[ib];
+            let db = [sc & 0xf, sc >> 4].map(|s| delta * (0.5 + s as f32) * 0.25);
+            for (l, y) in y.chunks_exact_mut(8).enumerate() {
+                let q = self.qs[4 * ib + l];
+                let grid = IQ2XS_GRID[(q & 511) as usize];
+                dequantize_grid(db[l / 2], grid, (q >> 9) as u8, y)
+            }
+        }
+        ans
     }
 }
+
+#[test]
+fn test_iq2xs() {
+    assert_eq!(size_of::<IQ2XS>(), 74);
+    crate::test_utils::test::<256, IQ2XS>(8e-1, 0.);
+    crate::test_utils::test_total_error::<256, IQ2XS>(7e-3);
+}
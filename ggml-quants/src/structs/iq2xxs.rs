@@ -1,5 +1,11 @@
-﻿use super::{f16, _256};
+﻿use super::{
+    f16,
+    iq2::{dequantize_grid, quantize_iq2, Codebook, IQ2XXS_GRID},
+    k_quants::nearest_int,
+    _256, _32,
+};
 use crate::{DataBlock, Quantize};
+use std::sync::LazyLock;
 
 #[repr(C)]
 pub struct IQ2XXS {
@@ -15,11 +21,66 @@ impl_data_block! {
     }
 }
 
+static CODEBOOK: LazyLock<Codebook> = LazyLock::new(|| Codebook::new(&IQ2XXS_GRID));
+
 impl Quantize<f32, _256> for IQ2XXS {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    /// 没有重要性矩阵时以 `sqrt(sigma2 + x²)` 为权重，结果与 ggml 不逐位一致。
+    fn quantize(data: &[f32; _256]) -> Self {
+        let sigma2 = data.iter().map(|x| x * x).sum::<f32>() / _256 as f32;
+        // 每 32 个数占 2 个 u32，前一个存 4 个 8 位格点序号，后一个存 4 组 7 位符号和 4 位 scale
+        let mut q2 = [0u32; _256 / 16];
+        let mut scales = [0f32; _256 / _32];
+        for (ib, xb) in data.chunks_exact(_32).enumerate() {
+            let mut index = [0; 4];
+            let mut signs = [0; 4];
+            scales[ib] = quantize_iq2(&CODEBOOK, xb, sigma2, &mut index, &mut signs);
+            for k in 0..4 {
+                q2[2 * ib] |= (index[k] as u32) << (8 * k);
+                q2[2 * ib + 1] |= (signs[k] as u32) << (7 * k)
+            }
+        }
+
+        let max_scale = scales.iter().fold(0f32, |max, &s| max.max(s));
+        if max_scale == 0. {
+            return Self::ZEROS;
+        }
+        let d = max_scale / 31.;
+        let id = d.recip();
+        for (ib, &scale) in scales.iter().enumerate() {
+            let l = nearest_int(0.5 * (id * scale - 1.)).clamp(0, 15);
+            q2[2 * ib + 1] |= (l as u32) << 28
+        }
+
+        let mut qs = [0; _256 / 8];
+        for (i, q2) in q2.into_iter().enumerate() {
+            qs[2 * i] = q2 as u16;
+            qs[2 * i + 1] = (q2 >> 16) as u16
+        }
+        Self {
+            delta: f16::from_f32(d),
+            qs,
+        }
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let delta = self.delta.to_f32();
+        let mut ans = [0.; _256];
+        for (y, qs) in ans.chunks_exact_mut(_32).zip(self.qs.chunks_exact(4)) {
+            let index = qs[0] as u32 | (qs[1] as u32) << 16;
+            let aux = qs[2] as u32 | (qs[3] as u32) << 16;
+            let db = delta * (0.5 + (aux >> 28) as f32) * 0.25;
+            for (l, y) in y.chunks_exact_mut(8).enumerate() {
+                let grid = IQ2XXS_GRID[(index >> (8 * l) & 0xff) as usize];
+                dequantize_grid(db, grid, (aux >> (7 * l) & 127) as u8, y)
+            }
+        }
+        ans
     }
 }
+
+#[test]
+fn test_iq2xxs() {
+    assert_eq!(size_of::<IQ2XXS>(), 66);
+    crate::test_utils::test::<256, IQ2XXS>(8e-1, 0.);
+    crate::test_utils::test_total_error::<256, IQ2XXS>(7.5e-3);
+}
@@ -4,7 +4,7 @@ use crate::{DataBlock, Quantize};
 #[repr(C)]
 pub struct IQ3XXS {
     delta: f16,
-    qs: [u16; 3 * _256 / 8],
+    qs: [u8; 3 * _256 / 8],
 }
 
 impl_data_block! {
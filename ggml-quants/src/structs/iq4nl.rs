@@ -1,10 +1,15 @@
-﻿use super::{f16, _32};
+﻿use super::{
+    f16,
+    k_quants::{nearest_int, GROUP_MAX_EPS},
+    _256, _32,
+};
 use crate::{DataBlock, Quantize};
+use std::iter::zip;
 
 #[repr(C)]
 pub struct IQ4NL {
     delta: f16,
-    qs: [u16; _32 / 2],
+    qs: [u8; _32 / 2],
 }
 
 impl_data_block! {
@@ -15,11 +20,165 @@ impl_data_block! {
     }
 }
 
+/// 非线性 4 位量化的 16 个量化值
+pub(super) const KVALUES_IQ4NL: [i8; 16] = [
+    -127, -104, -83, -65, -49, -35, -22, -10, 1, 13, 25, 38, 53, 69, 89, 113,
+];
+
+/// 在升序排列的 `values` 中找到最接近 `x` 的值的序号
+pub(super) fn best_index_int8(values: &[i8], x: f32) -> usize {
+    let n = values.len();
+    if x <= values[0] as f32 {
+        return 0;
+    }
+    if x >= values[n - 1] as f32 {
+        return n - 1;
+    }
+    let (mut ml, mut mu) = (0, n - 1);
+    while mu - ml > 1 {
+        let mav = (ml + mu) / 2;
+        if x < values[mav] as f32 {
+            mu = mav
+        } else {
+            ml = mav
+        }
+    }
+    if x - (values[mu - 1] as f32) < values[mu] as f32 - x {
+        mu - 1
+    } else {
+        mu
+    }
+}
+
+/// IQ4NL 和 IQ4XS 共用的量化过程，即 ggml 的 `quantize_row_iq4_nl_impl`。
+///
+/// 每 32 个数一组搜索 scale，`scales` 为 `None` 时 `x` 只有一组，直接以 f16 保存 scale，
+/// 否则以 6 位量化每组的 scale，低 4 位和高 2 位分别存入 `scales_l` 和 `scales_h`。
+/// 量化值写入 `qs`，返回 delta。
+pub(super) fn quantize_iq4(x: &[f32], qs: &mut [u8], scales: Option<(&mut u16, &mut [u8])>) -> f16 {
+    const NTRY: i32 = 7;
+    let values = &KVALUES_IQ4NL;
+
+    let mut l = [0u8; _256];
+    let l = &mut l[..x.len()];
+    let mut block_scales = [0f32; _256 / _32];
+    let block_scales = &mut block_scales[..x.len() / _32];
+
+    let mut max_scale = 0f32;
+    let mut amax_scale = 0f32;
+    for (xb, scale) in zip(x.chunks_exact(_32), &mut *block_scales) {
+        let mut amax = 0f32;
+        let mut max = 0f32;
+        for &x in xb {
+            if x.abs() > amax {
+                amax = x.abs();
+                max = x;
+            }
+        }
+        if amax < GROUP_MAX_EPS {
+            *scale = 0.;
+            continue;
+        }
+
+        // 以 x² 为权重，求 scale 使加权误差最小
+        let sums = |id: f32| {
+            let mut sumqx = 0f32;
+            let mut sumq2 = 0f32;
+            for &x in xb {
+                let q = values[best_index_int8(values, id * x)] as f32;
+                let w = x * x;
+                sumqx += w * q * x;
+                sumq2 += w * q * q;
+            }
+            (sumqx, sumq2)
+        };
+        let (sumqx, sumq2) = sums((-max / values[0] as f32).recip());
+        let mut d = sumqx / sumq2;
+        let mut best = d * sumqx;
+        for itry in -NTRY..=NTRY {
+            let (sumqx, sumq2) = sums((itry + values[0] as i32) as f32 / max);
+            if sumq2 > 0. && sumqx * sumqx > best * sumq2 {
+                d = sumqx / sumq2;
+                best = d * sumqx;
+            }
+        }
+        *scale = d;
+        if d.abs() > amax_scale {
+            amax_scale = d.abs();
+            max_scale = d;
+        }
+    }
+
+    let delta = match scales {
+        Some((scales_h, scales_l)) => {
+            let d = -max_scale / 32.;
+            let id = if d != 0. { d.recip() } else { 0. };
+            *scales_h = 0;
+            scales_l.fill(0);
+            for (ib, (xb, lb)) in zip(x.chunks_exact(_32), l.chunks_exact_mut(_32)).enumerate() {
+                let ls = nearest_int(id * block_scales[ib]).clamp(-32, 31);
+                let dl = d * ls as f32;
+                let idl = if dl != 0. { dl.recip() } else { 0. };
+                for (l, &x) in zip(lb, xb) {
+                    *l = best_index_int8(values, idl * x) as _
+                }
+                let ls = (ls + 32) as u8;
+                scales_l[ib / 2] |= (ls & 0xf) << (4 * (ib % 2));
+                *scales_h |= ((ls >> 4) as u16) << (2 * ib)
+            }
+            d
+        }
+        None => {
+            let d = block_scales[0];
+            let id = if d != 0. { d.recip() } else { 0. };
+            for (l, &x) in zip(&mut *l, x) {
+                *l = best_index_int8(values, id * x) as _
+            }
+            d
+        }
+    };
+
+    // 每 32 个数一组，前 16 个存低 4 位，后 16 个存高 4 位
+    for (q, l) in zip(qs.chunks_exact_mut(_32 / 2), l.chunks_exact(_32)) {
+        let (l, h) = l.split_at(_32 / 2);
+        for i in 0.._32 / 2 {
+            q[i] = l[i] | (h[i] << 4)
+        }
+    }
+    f16::from_f32(delta)
+}
+
+/// 以 `delta` 反量化 32 个数
+pub(super) fn dequantize_iq4(delta: f32, qs: &[u8], y: &mut [f32]) {
+    let (l, h) = y.split_at_mut(_32 / 2);
+    for (i, &q) in qs.iter().enumerate() {
+        l[i] = delta * KVALUES_IQ4NL[(q & 0xf) as usize] as f32;
+        h[i] = delta * KVALUES_IQ4NL[(q >> 4) as usize] as f32;
+    }
+}
+
 impl Quantize<f32, _32> for IQ4NL {
-    fn quantize(_data: &[f32; _32]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _32]) -> Self {
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _32)
+        }
+
+        let mut qs = [0; _32 / 2];
+        let delta = quantize_iq4(data, &mut qs, None);
+        Self { delta, qs }
     }
+
     fn dequantize(&self) -> [f32; _32] {
-        todo!()
+        let mut ans = [0.; _32];
+        dequantize_iq4(self.delta.to_f32(), &self.qs, &mut ans);
+        ans
     }
 }
+
+#[test]
+fn test_iq4nl() {
+    assert_eq!(size_of::<IQ4NL>(), 18);
+    crate::test_utils::test::<32, IQ4NL>(1.5e-1, 0.);
+    crate::test_utils::test_total_error::<32, IQ4NL>(2e-3);
+}
@@ -1,12 +1,17 @@
-﻿use super::{f16, _256};
+﻿use super::{
+    f16,
+    iq4nl::{dequantize_iq4, quantize_iq4},
+    _256, _32,
+};
 use crate::{DataBlock, Quantize};
+use std::iter::zip;
 
 #[repr(C)]
 pub struct IQ4XS {
     delta: f16,
     scales_h: u16,
     scales_l: [u8; _256 / 64],
-    qs: [u16; _256 / 2],
+    qs: [u8; _256 / 2],
 }
 
 impl_data_block! {
@@ -20,10 +25,41 @@ impl_data_block! {
 }
 
 impl Quantize<f32, _256> for IQ4XS {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _256]) -> Self {
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        let mut ans = Self::ZEROS;
+        ans.delta = quantize_iq4(
+            data,
+            &mut ans.qs,
+            Some((&mut ans.scales_h, &mut ans.scales_l)),
+        );
+        ans
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let delta = self.delta.to_f32();
+
+        let mut ans = [0.; _256];
+        for (ib, (qs, y)) in
+            zip(self.qs.chunks_exact(_32 / 2), ans.chunks_exact_mut(_32)).enumerate()
+        {
+            // 每组 6 位的 scale，低 4 位在 scales_l，高 2 位在 scales_h
+            let l = (self.scales_l[ib / 2] >> (4 * (ib % 2))) & 0xf;
+            let h = ((self.scales_h >> (2 * ib)) & 3) as u8;
+            let ls = (l | (h << 4)) as i32 - 32;
+            dequantize_iq4(delta * ls as f32, qs, y)
+        }
+        ans
     }
 }
+
+#[test]
+fn test_iq4xs() {
+    assert_eq!(size_of::<IQ4XS>(), 136);
+    crate::test_utils::test::<256, IQ4XS>(1.5e-1, 0.);
+    crate::test_utils::test_total_error::<256, IQ4XS>(2e-3);
+}
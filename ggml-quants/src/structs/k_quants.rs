@@ -0,0 +1,368 @@
+//! k-quants 共用的量化算法，与 ggml 的参考实现一致
+
+use super::{DeltaMin, _256, _32};
+use std::{array::from_fn, iter::zip};
+
+/// 绝对值最大值小于此值的组视为全 0
+pub(super) const GROUP_MAX_EPS: f32 = 1e-15;
+
+/// 四舍五入到整数，平局取偶，与 ggml 的 `nearest_int` 一致
+#[inline]
+pub(super) fn nearest_int(x: f32) -> i32 {
+    x.round_ties_even() as i32
+}
+
+/// 以加权误差最小为目标，迭代搜索一组数的非对称量化参数，即 ggml 的 `make_qkx2_quants`。
+///
+/// 量化值写入 `l`，返回 `(scale, min)`，反量化为 `scale * l - min`。
+#[allow(clippy::too_many_arguments)]
+pub(super) fn make_qkx2_quants(
+    nmax: u8,
+    x: &[f32],
+    weights: &[f32],
+    l: &mut [u8],
+    rmin: f32,
+    rdelta: f32,
+    nstep: usize,
+    use_mad: bool,
+) -> (f32, f32) {
+    let n = x.len();
+    let nmax_ = nmax as f32;
+
+    let mut min = x[0];
+    let mut max = x[0];
+    let mut sum_w = weights[0];
+    let mut sum_x = sum_w * x[0];
+    for i in 1..n {
+        min = min.min(x[i]);
+        max = max.max(x[i]);
+        let w = weights[i];
+        sum_w += w;
+        sum_x += w * x[i];
+    }
+    if min > 0. {
+        min = 0.
+    }
+    if max == min {
+        l.fill(0);
+        return (0., -min);
+    }
+
+    let error = |diff: f32| if use_mad { diff.abs() } else { diff * diff };
+
+    let mut iscale = nmax_ / (max - min);
+    let mut scale = iscale.recip();
+    let mut best_mad = 0.;
+    for i in 0..n {
+        l[i] = nearest_int(iscale * (x[i] - min)).clamp(0, nmax as _) as _;
+        best_mad += weights[i] * error(scale * l[i] as f32 + min - x[i]);
+    }
+    if nstep < 1 {
+        return (scale, -min);
+    }
+
+    let mut laux = [0u8; _32];
+    let laux = &mut laux[..n];
+    for is in 0..=nstep {
+        iscale = (rmin + rdelta * is as f32 + nmax_) / (max - min);
+        let mut sum_l = 0.;
+        let mut sum_l2 = 0.;
+        let mut sum_xl = 0.;
+        for i in 0..n {
+            let l = nearest_int(iscale * (x[i] - min)).clamp(0, nmax as _);
+            laux[i] = l as _;
+            let w = weights[i];
+            let l = l as f32;
+            sum_l += w * l;
+            sum_l2 += w * l * l;
+            sum_xl += w * l * x[i];
+        }
+        let d = sum_w * sum_l2 - sum_l * sum_l;
+        if d > 0. {
+            let mut this_scale = (sum_w * sum_xl - sum_x * sum_l) / d;
+            let mut this_min = (sum_l2 * sum_x - sum_l * sum_xl) / d;
+            if this_min > 0. {
+                this_min = 0.;
+                this_scale = sum_xl / sum_l2;
+            }
+            let mut mad = 0.;
+            for i in 0..n {
+                mad += weights[i] * error(this_scale * laux[i] as f32 + this_min - x[i]);
+            }
+            if mad < best_mad {
+                l.copy_from_slice(laux);
+                best_mad = mad;
+                scale = this_scale;
+                min = this_min;
+            }
+        }
+    }
+    (scale, -min)
+}
+
+/// 以 `x²` 为权重搜索一组数的对称量化参数，即 ggml 的 `make_qx_quants` 在 `rmse_type = 1` 且无重要性矩阵时的行为。
+///
+/// 量化值加上 `nmax` 后写入 `l`，返回 scale，反量化为 `scale * (l - nmax)`。
+pub(super) fn make_qx_quants(nmax: i32, x: &[f32], l: &mut [u8]) -> f32 {
+    let mut max = 0f32;
+    let mut amax = 0f32;
+    for &x in x {
+        if x.abs() > amax {
+            amax = x.abs();
+            max = x;
+        }
+    }
+    if amax < GROUP_MAX_EPS {
+        l.fill(0);
+        return 0.;
+    }
+
+    let quant = |iscale: f32, x: f32| nearest_int(iscale * x).clamp(-nmax, nmax - 1);
+    let sums = |iscale: f32| {
+        let mut sumlx = 0f32;
+        let mut suml2 = 0f32;
+        for &x in x {
+            let l = quant(iscale, x) as f32;
+            let w = x * x;
+            sumlx += w * x * l;
+            suml2 += w * l * l;
+        }
+        (sumlx, suml2)
+    };
+    let mut fill = |iscale: f32| {
+        for (l, &x) in l.iter_mut().zip(x) {
+            *l = (quant(iscale, x) + nmax) as _
+        }
+    };
+
+    let iscale = -nmax as f32 / max;
+    fill(iscale);
+    let (sumlx, suml2) = sums(iscale);
+    let mut scale = if suml2 != 0. { sumlx / suml2 } else { 0. };
+    let mut best = scale * sumlx;
+    for is in (-9..=9).filter(|&is| is != 0) {
+        let iscale = -(nmax as f32 + 0.1 * is as f32) / max;
+        let (sumlx, suml2) = sums(iscale);
+        if suml2 > 0. && sumlx * sumlx > best * suml2 {
+            fill(iscale);
+            scale = sumlx / suml2;
+            best = scale * sumlx;
+        }
+    }
+    scale
+}
+
+/// 以 `x²` 为权重逐个调整量化值，搜索一组数的对称量化参数，即 ggml 的 `make_q3_quants` 在 `do_rmse = true` 时的行为。
+///
+/// 量化值加上 `nmax` 后写入 `l`，返回 scale，反量化为 `scale * (l - nmax)`。
+pub(super) fn make_q3_quants(nmax: i32, x: &[f32], l: &mut [u8]) -> f32 {
+    let mut max = 0f32;
+    let mut amax = 0f32;
+    for &x in x {
+        if x.abs() > amax {
+            amax = x.abs();
+            max = x;
+        }
+    }
+    if amax < GROUP_MAX_EPS {
+        l.fill(0);
+        return 0.;
+    }
+
+    let iscale = -nmax as f32 / max;
+    let mut q = [0i32; 16];
+    let q = &mut q[..x.len()];
+    let mut sumlx = 0f32;
+    let mut suml2 = 0f32;
+    for (q, &x) in zip(&mut *q, x) {
+        *q = nearest_int(iscale * x).clamp(-nmax, nmax - 1);
+        let w = x * x;
+        sumlx += w * x * *q as f32;
+        suml2 += w * *q as f32 * *q as f32;
+    }
+    for _ in 0..5 {
+        let mut changed = false;
+        for (q, &x) in zip(&mut *q, x) {
+            let w = x * x;
+            let mut slx = sumlx - w * x * *q as f32;
+            if slx > 0. {
+                let mut sl2 = suml2 - w * *q as f32 * *q as f32;
+                let new_q = nearest_int(x * sl2 / slx).clamp(-nmax, nmax - 1);
+                if new_q != *q {
+                    slx += w * x * new_q as f32;
+                    sl2 += w * new_q as f32 * new_q as f32;
+                    if sl2 > 0. && slx * slx * suml2 > sumlx * sumlx * sl2 {
+                        *q = new_q;
+                        sumlx = slx;
+                        suml2 = sl2;
+                        changed = true
+                    }
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    for (l, q) in zip(l, q) {
+        *l = (*q + nmax) as _
+    }
+    sumlx / suml2
+}
+
+/// 以 `weight` 为权重搜索一组非负数的量化参数并逐个调整量化值，即 ggml 的 `make_qp_quants`。
+///
+/// 量化值写入 `l`，取值 `0..=nmax`，返回 scale，反量化为 `scale * l`。
+pub(super) fn make_qp_quants(nmax: i32, x: &[f32], l: &mut [u8], weight: &[f32]) -> f32 {
+    let max = x.iter().fold(0f32, |max, &x| max.max(x));
+    if max == 0. {
+        l.fill(0);
+        return 0.;
+    }
+
+    let quant = |iscale: f32, x: f32| nearest_int(iscale * x).clamp(0, nmax);
+    let mse = |iscale: f32| {
+        let scale = iscale.recip();
+        zip(x, weight)
+            .map(|(&x, &w)| {
+                let diff = x - scale * quant(iscale, x) as f32;
+                w * diff * diff
+            })
+            .sum::<f32>()
+    };
+
+    let mut iscale = nmax as f32 / max;
+    let mut best_mse = mse(iscale);
+    for is in (-4..=4).filter(|&is| is != 0) {
+        let iscale_is = (0.1 * is as f32 + nmax as f32) / max;
+        let mse = mse(iscale_is);
+        if mse < best_mse {
+            best_mse = mse;
+            iscale = iscale_is
+        }
+    }
+
+    let mut sumlx = 0f32;
+    let mut suml2 = 0f32;
+    for ((l, &x), &w) in zip(zip(&mut *l, x), weight) {
+        let q = quant(iscale, x);
+        *l = q as _;
+        sumlx += w * x * q as f32;
+        suml2 += w * (q * q) as f32
+    }
+    for _ in 0..5 {
+        let mut changed = false;
+        for ((l, &x), &w) in zip(zip(&mut *l, x), weight) {
+            let q = *l as f32;
+            let mut slx = sumlx - w * x * q;
+            let mut sl2 = suml2 - w * q * q;
+            if slx > 0. && sl2 > 0. {
+                let new_l = nearest_int(x * sl2 / slx).clamp(0, nmax);
+                if new_l != *l as i32 {
+                    slx += w * x * new_l as f32;
+                    sl2 += w * (new_l * new_l) as f32;
+                    if slx * slx * suml2 > sumlx * sumlx * sl2 {
+                        *l = new_l as _;
+                        sumlx = slx;
+                        suml2 = sl2;
+                        changed = true
+                    }
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    sumlx / suml2
+}
+
+/// 读取第 `j` 组以 6 位打包的 scale 和 min
+#[inline]
+pub(super) fn get_scale_min_k4(j: usize, q: &[u8; 12]) -> (u8, u8) {
+    if j < 4 {
+        (q[j] & 63, q[j + 4] & 63)
+    } else {
+        (
+            (q[j + 4] & 0xf) | ((q[j - 4] >> 6) << 4),
+            (q[j + 4] >> 4) | ((q[j] >> 6) << 4),
+        )
+    }
+}
+
+/// 将 8 组 6 位的 scale 和 min 打包为 12 字节
+fn pack_scale_min_k4(scales: [u8; 8], mins: [u8; 8]) -> [u8; 12] {
+    let mut q = [0; 12];
+    for j in 0..8 {
+        let (ls, lm) = (scales[j], mins[j]);
+        if j < 4 {
+            q[j] = ls;
+            q[j + 4] = lm;
+        } else {
+            q[j + 4] = (ls & 0xf) | ((lm & 0xf) << 4);
+            q[j - 4] |= (ls >> 4) << 6;
+            q[j] |= (lm >> 4) << 6;
+        }
+    }
+    q
+}
+
+/// Q4K 和 Q5K 共用的量化过程。
+///
+/// 每 32 个数一组由 [make_qkx2_quants] 求出 scale 和 min，以 6 位量化后打包，
+/// 再用量化后的 scale 和 min 重新量化每个数，返回 `(delta_min, scales, 量化值)`。
+pub(super) fn quantize_qkx(
+    x: &[f32; _256],
+    nmax: u8,
+    rmin: f32,
+    nstep: usize,
+) -> (DeltaMin, [u8; 12], [u8; _256]) {
+    let mut l = [0u8; _256];
+    let mut scales = [0f32; 8];
+    let mut mins = [0f32; 8];
+    let mut max_scale = 0f32;
+    let mut max_min = 0f32;
+    for j in 0..8 {
+        let x = &x[_32 * j..][.._32];
+        let sum_x2 = x.iter().map(|x| x * x).sum::<f32>();
+        let av_x = (sum_x2 / _32 as f32).sqrt();
+        let weights: [f32; _32] = from_fn(|i| av_x + x[i].abs());
+        let (scale, min) = make_qkx2_quants(
+            nmax,
+            x,
+            &weights,
+            &mut l[_32 * j..][.._32],
+            rmin,
+            0.1,
+            nstep,
+            false,
+        );
+        scales[j] = scale;
+        mins[j] = min;
+        max_scale = max_scale.max(scale);
+        max_min = max_min.max(min);
+    }
+
+    let inv_scale = if max_scale > 0. { 63. / max_scale } else { 0. };
+    let inv_min = if max_min > 0. { 63. / max_min } else { 0. };
+    let packed = pack_scale_min_k4(
+        scales.map(|s| nearest_int(inv_scale * s).min(63) as u8),
+        mins.map(|m| nearest_int(inv_min * m).min(63) as u8),
+    );
+    let delta_min = DeltaMin::new(max_scale / 63., max_min / 63.);
+    let (delta, min) = delta_min.to_f32();
+
+    for j in 0..8 {
+        let (sc, m) = get_scale_min_k4(j, &packed);
+        let d = delta * sc as f32;
+        if d == 0. {
+            continue;
+        }
+        let dm = min * m as f32;
+        for i in _32 * j.._32 * (j + 1) {
+            l[i] = nearest_int((x[i] + dm) / d).clamp(0, nmax as _) as u8;
+        }
+    }
+
+    (delta_min, packed, l)
+}
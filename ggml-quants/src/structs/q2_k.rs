@@ -1,5 +1,9 @@
-﻿use super::{DeltaMin, _256};
+﻿use super::{
+    k_quants::{make_qkx2_quants, nearest_int},
+    DeltaMin, _256, _32,
+};
 use crate::{DataBlock, Quantize};
+use std::{array::from_fn, iter::zip};
 
 #[repr(C)]
 pub struct Q2K {
@@ -18,10 +22,78 @@ impl_data_block! {
 }
 
 impl Quantize<f32, _256> for Q2K {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _256]) -> Self {
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        // 每 16 个数一组求 scale 和 min，再分别以 4 位量化
+        let mut l = [0u8; _256];
+        let mut scales = [0f32; _256 / 16];
+        let mut mins = [0f32; _256 / 16];
+        for (j, (x, l)) in zip(data.chunks_exact(16), l.chunks_exact_mut(16)).enumerate() {
+            let weights: [f32; 16] = from_fn(|i| x[i].abs());
+            (scales[j], mins[j]) = make_qkx2_quants(3, x, &weights, l, -0.5, 0.1, 15, true);
+        }
+        let max_scale = scales.iter().fold(0f32, |acc, &x| acc.max(x));
+        let max_min = mins.iter().fold(0f32, |acc, &x| acc.max(x));
+
+        let quant = |max: f32, x: [f32; _256 / 16]| {
+            if max > 0. {
+                let iscale = 15. / max;
+                (max / 15., x.map(|x| nearest_int(iscale * x) as u8))
+            } else {
+                (0., [0; _256 / 16])
+            }
+        };
+        let (delta, ls) = quant(max_scale, scales);
+        let (min, lm) = quant(max_min, mins);
+        let delta_min = DeltaMin::new(delta, min);
+        let scales: [u8; _256 / 16] = from_fn(|j| ls[j] | (lm[j] << 4));
+
+        let (delta, min) = delta_min.to_f32();
+        for (j, (x, l)) in zip(data.chunks_exact(16), l.chunks_exact_mut(16)).enumerate() {
+            let d = delta * (scales[j] & 0xf) as f32;
+            if d == 0. {
+                continue;
+            }
+            let dm = min * (scales[j] >> 4) as f32;
+            for (l, &x) in zip(l, x) {
+                *l = nearest_int((x + dm) / d).clamp(0, 3) as _
+            }
+        }
+
+        // 每 128 个数一组，分为 4 段，依次存入每个字节的 2 位
+        let mut qs = [0; _256 / 4];
+        for (q, l) in zip(qs.chunks_exact_mut(_32), l.chunks_exact(4 * _32)) {
+            for i in 0.._32 {
+                q[i] = l[i] | (l[i + _32] << 2) | (l[i + 2 * _32] << 4) | (l[i + 3 * _32] << 6)
+            }
+        }
+
+        Self {
+            scales,
+            qs,
+            delta_min,
+        }
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let (delta, min) = self.delta_min.to_f32();
+
+        let mut ans = [0.; _256];
+        for (i, y) in ans.iter_mut().enumerate() {
+            let sc = self.scales[i / 16];
+            let q = (self.qs[i / 128 * _32 + i % _32] >> (i % 128 / _32 * 2)) & 3;
+            *y = delta * (sc & 0xf) as f32 * q as f32 - min * (sc >> 4) as f32
+        }
+        ans
     }
 }
+
+#[test]
+fn test_q2k() {
+    crate::test_utils::test::<256, Q2K>(3e-1, 0.);
+    crate::test_utils::test_total_error::<256, Q2K>(7.5e-3);
+}
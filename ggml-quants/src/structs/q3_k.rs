@@ -1,5 +1,10 @@
-﻿use super::{f16, _256};
+﻿use super::{
+    f16,
+    k_quants::{make_q3_quants, nearest_int},
+    _256, _32,
+};
 use crate::{DataBlock, Quantize};
+use std::iter::zip;
 
 #[repr(C)]
 pub struct Q3K {
@@ -19,11 +24,103 @@ impl_data_block! {
     }
 }
 
+impl Q3K {
+    /// 读取第 `j` 组的 6 位 scale，低 4 位在前 8 字节，高 2 位在后 4 字节
+    #[inline]
+    fn scale(&self, j: usize) -> i8 {
+        let l = if j < 8 {
+            self.scales[j] & 0xf
+        } else {
+            self.scales[j - 8] >> 4
+        };
+        let h = (self.scales[8 + j % 4] >> (2 * (j / 4))) & 3;
+        (l | (h << 4)) as i8 - 32
+    }
+}
+
 impl Quantize<f32, _256> for Q3K {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _256]) -> Self {
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        // 每 16 个数一组求 scale，再以 6 位量化 scale
+        let mut l = [0u8; _256];
+        let mut scales = [0f32; _256 / 16];
+        let mut max_scale = 0f32;
+        let mut amax = 0f32;
+        for (j, (x, l)) in zip(data.chunks_exact(16), l.chunks_exact_mut(16)).enumerate() {
+            let scale = make_q3_quants(4, x, l);
+            scales[j] = scale;
+            if scale.abs() > amax {
+                amax = scale.abs();
+                max_scale = scale;
+            }
+        }
+
+        let mut ans = Self::ZEROS;
+        if max_scale != 0. {
+            let iscale = -32. / max_scale;
+            for (j, &s) in scales.iter().enumerate() {
+                let l = (nearest_int(iscale * s).clamp(-32, 31) + 32) as u8;
+                if j < 8 {
+                    ans.scales[j] = l & 0xf
+                } else {
+                    ans.scales[j - 8] |= (l & 0xf) << 4
+                }
+                ans.scales[8 + j % 4] |= (l >> 4) << (2 * (j / 4))
+            }
+            ans.delta = f16::from_f32(iscale.recip())
+        }
+
+        let delta = ans.delta.to_f32();
+        for (j, (x, l)) in zip(data.chunks_exact(16), l.chunks_exact_mut(16)).enumerate() {
+            let d = delta * ans.scale(j) as f32;
+            if d == 0. {
+                continue;
+            }
+            for (l, &x) in zip(l, x) {
+                *l = (nearest_int(x / d).clamp(-4, 3) + 4) as _
+            }
+        }
+
+        // 第 3 位存入 hmask，每 32 个数占用每个字节的 1 位
+        for (i, l) in l.iter_mut().enumerate() {
+            if *l > 3 {
+                ans.hmask[i % _32] |= 1 << (i / _32);
+                *l -= 4
+            }
+        }
+        // 每 128 个数一组，分为 4 段，依次存入每个字节的 2 位
+        for (q, l) in zip(ans.qs.chunks_exact_mut(_32), l.chunks_exact(4 * _32)) {
+            for i in 0.._32 {
+                q[i] = l[i] | (l[i + _32] << 2) | (l[i + 2 * _32] << 4) | (l[i + 3 * _32] << 6)
+            }
+        }
+        ans
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let delta = self.delta.to_f32();
+        let scales: [i8; _256 / 16] = std::array::from_fn(|j| self.scale(j));
+
+        let mut ans = [0.; _256];
+        for (i, y) in ans.iter_mut().enumerate() {
+            let q = (self.qs[i / 128 * _32 + i % _32] >> (i % 128 / _32 * 2)) & 3;
+            let h = if self.hmask[i % _32] & (1 << (i / _32)) != 0 {
+                0
+            } else {
+                4
+            };
+            *y = delta * scales[i / 16] as f32 * (q as i8 - h) as f32
+        }
+        ans
     }
 }
+
+#[test]
+fn test_q3k() {
+    crate::test_utils::test::<256, Q3K>(1.5e-1, 0.);
+    crate::test_utils::test_total_error::<256, Q3K>(4e-3);
+}
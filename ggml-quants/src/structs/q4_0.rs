@@ -55,5 +55,5 @@ impl Quantize<f32, _32> for Q4_0 {
 
 #[test]
 fn test_q4_0() {
-    crate::test_utils::test::<32_, Q4_0>(8e-2, 0.);
+    crate::test_utils::test::<32, Q4_0>(8e-2, 0.);
 }
@@ -1,5 +1,9 @@
-﻿use super::{DeltaMin, _256};
+﻿use super::{
+    k_quants::{get_scale_min_k4, quantize_qkx},
+    DeltaMin, _256, _32,
+};
 use crate::{DataBlock, Quantize};
+use std::iter::zip;
 
 #[repr(C)]
 pub struct Q4K {
@@ -18,10 +22,51 @@ impl_data_block! {
 }
 
 impl Quantize<f32, _256> for Q4K {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _256]) -> Self {
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        let (delta_min, scales, l) = quantize_qkx(data, 15, -1., 20);
+        // 每 64 个数一组，前 32 个存低 4 位，后 32 个存高 4 位
+        let mut qs = [0; _256 / 2];
+        for (q, l) in zip(qs.chunks_exact_mut(_32), l.chunks_exact(2 * _32)) {
+            let (l, h) = l.split_at(_32);
+            for i in 0.._32 {
+                q[i] = l[i] | (h[i] << 4)
+            }
+        }
+        Self {
+            delta_min,
+            scales,
+            qs,
+        }
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let (delta, min) = self.delta_min.to_f32();
+        let scale_min = |j| {
+            let (sc, m) = get_scale_min_k4(j, &self.scales);
+            (delta * sc as f32, min * m as f32)
+        };
+
+        let mut ans = [0.; _256];
+        for (j, (q, y)) in zip(self.qs.chunks_exact(_32), ans.chunks_exact_mut(2 * _32)).enumerate()
+        {
+            let (d1, m1) = scale_min(2 * j);
+            let (d2, m2) = scale_min(2 * j + 1);
+            let (l, h) = y.split_at_mut(_32);
+            for i in 0.._32 {
+                l[i] = d1 * (q[i] & 0xf) as f32 - m1;
+                h[i] = d2 * (q[i] >> 4) as f32 - m2;
+            }
+        }
+        ans
     }
 }
+
+#[test]
+fn test_q4k() {
+    crate::test_utils::test::<256, Q4K>(6e-2, 0.);
+}
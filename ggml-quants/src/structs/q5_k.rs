@@ -1,11 +1,13 @@
-﻿use super::_256;
+﻿use super::{
+    k_quants::{get_scale_min_k4, quantize_qkx},
+    DeltaMin, _256, _32,
+};
 use crate::{DataBlock, Quantize};
-use half::f16;
+use std::iter::zip;
 
 #[repr(C)]
 pub struct Q5K {
-    delta: f16,
-    min: f16,
+    delta_min: DeltaMin,
     scales: [u8; 12],
     qh: [u8; _256 / 8],
     qs: [u8; _256 / 2],
@@ -14,8 +16,7 @@ pub struct Q5K {
 impl_data_block! {
     Q5K = crate::types::Q5K;
     Self {
-        delta: f16::ZERO,
-        min: f16::ZERO,
+        delta_min: DeltaMin::ZERO,
         scales: [0; 12],
         qh: [0; _256 / 8],
         qs: [0; _256 / 2],
@@ -23,10 +24,56 @@ impl_data_block! {
 }
 
 impl Quantize<f32, _256> for Q5K {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _256]) -> Self {
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        let (delta_min, scales, l) = quantize_qkx(data, 31, -0.5, 15);
+        // 每 64 个数一组，前 32 个存低 4 位，后 32 个存高 4 位，
+        // 第 5 位存入 qh 中对应组的两个位
+        let mut qh = [0; _256 / 8];
+        let mut qs = [0; _256 / 2];
+        for (j, (q, l)) in zip(qs.chunks_exact_mut(_32), l.chunks_exact(2 * _32)).enumerate() {
+            let (l, h) = l.split_at(_32);
+            for i in 0.._32 {
+                q[i] = (l[i] & 0xf) | ((h[i] & 0xf) << 4);
+                qh[i] |= ((l[i] >> 4) << (2 * j)) | ((h[i] >> 4) << (2 * j + 1))
+            }
+        }
+        Self {
+            delta_min,
+            scales,
+            qh,
+            qs,
+        }
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let (delta, min) = self.delta_min.to_f32();
+        let scale_min = |j| {
+            let (sc, m) = get_scale_min_k4(j, &self.scales);
+            (delta * sc as f32, min * m as f32)
+        };
+
+        let mut ans = [0.; _256];
+        for (j, (q, y)) in zip(self.qs.chunks_exact(_32), ans.chunks_exact_mut(2 * _32)).enumerate()
+        {
+            let (d1, m1) = scale_min(2 * j);
+            let (d2, m2) = scale_min(2 * j + 1);
+            let (l, h) = y.split_at_mut(_32);
+            for i in 0.._32 {
+                let qh = self.qh[i] >> (2 * j);
+                l[i] = d1 * ((q[i] & 0xf) | ((qh & 1) << 4)) as f32 - m1;
+                h[i] = d2 * ((q[i] >> 4) | ((qh & 2) << 3)) as f32 - m2;
+            }
+        }
+        ans
     }
 }
+
+#[test]
+fn test_q5k() {
+    crate::test_utils::test::<256, Q5K>(4e-2, 0.);
+}
@@ -1,12 +1,16 @@
-﻿use super::_256;
+﻿use super::{
+    k_quants::{make_qx_quants, nearest_int, GROUP_MAX_EPS},
+    _256, _32,
+};
 use crate::{DataBlock, Quantize};
 use half::f16;
+use std::iter::zip;
 
 #[repr(C)]
 pub struct Q6K {
     ql: [u8; _256 / 2],
     qh: [u8; _256 / 4],
-    scales: [u8; _256 / 16],
+    scales: [i8; _256 / 16],
     delta: f16,
 }
 
@@ -21,10 +25,108 @@ impl_data_block! {
 }
 
 impl Quantize<f32, _256> for Q6K {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _256]) -> Self {
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        // 每 16 个数一组求 scale，再以 8 位量化 scale
+        let mut l = [0u8; _256];
+        let mut scales = [0f32; _256 / 16];
+        let mut max_scale = 0f32;
+        let mut max_abs_scale = 0f32;
+        for (j, (x, l)) in zip(data.chunks_exact(16), l.chunks_exact_mut(16)).enumerate() {
+            let scale = make_qx_quants(32, x, l);
+            scales[j] = scale;
+            if scale.abs() > max_abs_scale {
+                max_abs_scale = scale.abs();
+                max_scale = scale;
+            }
+        }
+        if max_abs_scale < GROUP_MAX_EPS {
+            return Self::ZEROS;
+        }
+
+        let iscale = -128. / max_scale;
+        let delta = f16::from_f32(iscale.recip());
+        let scales = scales.map(|s| nearest_int(iscale * s).min(127) as i8);
+        for (j, (x, l)) in zip(data.chunks_exact(16), l.chunks_exact_mut(16)).enumerate() {
+            let d = delta.to_f32() * scales[j] as f32;
+            if d == 0. {
+                continue;
+            }
+            for (l, &x) in zip(l, x) {
+                *l = (nearest_int(x / d).clamp(-32, 31) + 32) as _
+            }
+        }
+
+        // 每 128 个数一组，分为 4 段，低 4 位存入 ql，高 2 位存入 qh
+        let mut ql = [0; _256 / 2];
+        let mut qh = [0; _256 / 4];
+        for (l, (ql, qh)) in zip(
+            l.chunks_exact(4 * _32),
+            zip(ql.chunks_exact_mut(2 * _32), qh.chunks_exact_mut(_32)),
+        ) {
+            for i in 0.._32 {
+                let [q1, q2, q3, q4] = [0, 1, 2, 3].map(|k| l[k * _32 + i]);
+                ql[i] = (q1 & 0xf) | ((q3 & 0xf) << 4);
+                ql[i + _32] = (q2 & 0xf) | ((q4 & 0xf) << 4);
+                qh[i] = (q1 >> 4) | ((q2 >> 4) << 2) | ((q3 >> 4) << 4) | ((q4 >> 4) << 6);
+            }
+        }
+
+        Self {
+            ql,
+            qh,
+            scales,
+            delta,
+        }
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let delta = self.delta.to_f32();
+
+        let mut ans = [0.; _256];
+        for (y, (ql, (qh, sc))) in zip(
+            ans.chunks_exact_mut(4 * _32),
+            zip(
+                self.ql.chunks_exact(2 * _32),
+                zip(self.qh.chunks_exact(_32), self.scales.chunks_exact(8)),
+            ),
+        ) {
+            for i in 0.._32 {
+                let is = i / 16;
+                let q = [
+                    (ql[i] & 0xf) | ((qh[i] & 3) << 4),
+                    (ql[i + _32] & 0xf) | (((qh[i] >> 2) & 3) << 4),
+                    (ql[i] >> 4) | (((qh[i] >> 4) & 3) << 4),
+                    (ql[i + _32] >> 4) | (((qh[i] >> 6) & 3) << 4),
+                ];
+                for (k, q) in q.into_iter().enumerate() {
+                    y[k * _32 + i] = delta * sc[is + 2 * k] as f32 * (q as i8 - 32) as f32
+                }
+            }
+        }
+        ans
     }
 }
+
+#[test]
+fn test_q6k() {
+    crate::test_utils::test::<256, Q6K>(2e-2, 0.);
+}
+
+#[test]
+fn test_q6k_reference() {
+    // 与 llama.cpp 的 quantize_row_q6_K_ref 结果逐位比较
+    let data = std::array::from_fn(|i| ((i * 37 % 101) as f32 / 50. - 1.) * (1 + i / 64) as f32);
+    let q = Q6K::quantize(&data);
+    assert_eq!(q.delta.to_bits(), 0x9419);
+    assert_eq!(
+        q.scales,
+        [-32, 32, -30, 31, -62, 62, -62, 61, 96, 87, 94, 92, -128, 122, 125, -120]
+    );
+    assert_eq!(q.ql[..8], [0xd1, 0x58, 0xbf, 0x37, 0xbe, 0x25, 0xad, 0x04]);
+    assert_eq!(q.qh[..8], [0x18, 0xb1, 0xc6, 0x6c, 0xb1, 0x1b, 0x6c, 0x86]);
+}
@@ -45,5 +45,5 @@ impl Quantize<f32, _32> for Q8_0 {
 
 #[test]
 fn test_q8_0() {
-    crate::test_utils::test::<32_, Q8_0>(4e-3, 0.);
+    crate::test_utils::test::<32, Q8_0>(4e-3, 0.);
 }
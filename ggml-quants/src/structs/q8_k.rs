@@ -1,11 +1,10 @@
 ﻿use super::{max_by_abs, _256};
 use crate::{DataBlock, Quantize};
-use half::f16;
 use std::iter::zip;
 
 #[repr(C)]
 pub struct Q8K {
-    delta: f16,
+    delta: f32,
     quants: [i8; _256],
     sums: [i16; _256 / 16],
 }
@@ -13,7 +12,7 @@ pub struct Q8K {
 impl_data_block! {
     Q8K = crate::types::Q8K;
     Self {
-        delta: f16::ZERO,
+        delta: 0.,
         quants: [0; _256],
         sums: [0; _256 / 16],
     }
@@ -42,7 +41,7 @@ impl Quantize<f32, _256> for Q8K {
         }
 
         Self {
-            delta: f16::from_f32(delta),
+            delta,
             quants,
             sums,
         }
@@ -50,7 +49,6 @@ impl Quantize<f32, _256> for Q8K {
 
     #[inline]
     fn dequantize(&self) -> [f32; _256] {
-        let delta = self.delta.to_f32();
-        self.quants.map(|x| x as f32 * delta)
+        self.quants.map(|x| x as f32 * self.delta)
     }
 }
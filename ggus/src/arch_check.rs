@@ -0,0 +1,576 @@
+use crate::{
+    GGufHParams, GGufHParamsError, GGufMetaDataValueType as Ty, GGufMetaError, GGufMetaMap,
+    GGufMetaMapExt, GGufTensorKind as K, GGufTensorName, GGufTensorSuffix,
+};
+use indexmap::IndexMap;
+use std::fmt;
+
+/// A reason a model would not load as its `general.architecture` in llama.cpp, found by [GGufMetaMapExt::check_arch].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum GGufArchIssue {
+    UnsupportedArchitecture(String),
+    MissingKey(String),
+    /// The key is not of the type llama.cpp reads, which does not widen integers.
+    KeyTypeMismatch {
+        key: String,
+        expected: Ty,
+        found: Ty,
+    },
+    /// The key is an array whose elements are not of the type llama.cpp reads.
+    ArrTypeMismatch {
+        key: String,
+        expected: Ty,
+        found: Ty,
+    },
+    /// The key has the right type, but its value is not usable, such as zero heads.
+    InvalidKey(String),
+    MissingTensor(String),
+    /// The tensor is not used by the architecture, llama.cpp refuses files with unused tensors.
+    UnexpectedTensor(String),
+    ShapeMismatch {
+        name: String,
+        expected: Vec<u64>,
+        found: Vec<u64>,
+    },
+}
+
+impl fmt::Display for GGufArchIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedArchitecture(arch) => write!(f, "unsupported architecture: {arch}"),
+            Self::MissingKey(key) => write!(f, "missing meta key: {key}"),
+            Self::KeyTypeMismatch {
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "meta key {key} is {}, expected {}",
+                found.name(),
+                expected.name()
+            ),
+            Self::ArrTypeMismatch {
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "meta key {key} is an array of {}, expected {}",
+                found.name(),
+                expected.name()
+            ),
+            Self::InvalidKey(key) => write!(f, "invalid value of meta key {key}"),
+            Self::MissingTensor(name) => write!(f, "missing tensor: {name}"),
+            Self::UnexpectedTensor(name) => write!(f, "unexpected tensor: {name}"),
+            Self::ShapeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "tensor {name} has shape {found:?}, expected {expected:?}"
+            ),
+        }
+    }
+}
+
+pub(crate) fn check_arch<M, N, S>(
+    meta: &M,
+    tensors: impl IntoIterator<Item = (N, S)>,
+) -> Vec<GGufArchIssue>
+where
+    M: GGufMetaMap,
+    N: AsRef<str>,
+    S: AsRef<[u64]>,
+{
+    let mut ans = Vec::new();
+
+    let arch = match meta.general_architecture() {
+        Ok(arch) => arch,
+        Err(GGufMetaError::NotExist) => {
+            ans.push(GGufArchIssue::MissingKey("general.architecture".into()));
+            return ans;
+        }
+        Err(_) => {
+            check_key(meta, "general.architecture", STR, &mut ans);
+            return ans;
+        }
+    };
+    let Some(keys) = arch_keys(arch) else {
+        ans.push(GGufArchIssue::UnsupportedArchitecture(arch.into()));
+        return ans;
+    };
+
+    // 检查元信息的存在性和类型
+    let prefix = if arch == "clip" { "clip.vision" } else { arch };
+    for &(name, required, ty) in keys.iter().copied().flatten() {
+        let key = format!("{prefix}.{name}");
+        if meta.get(&key).is_none() {
+            if required {
+                ans.push(GGufArchIssue::MissingKey(key))
+            }
+        } else {
+            check_key(meta, &key, ty, &mut ans)
+        }
+    }
+    if arch != "clip" {
+        for &(key, ty) in TOKENIZER_KEYS {
+            if meta.get(key).is_none() {
+                ans.push(GGufArchIssue::MissingKey(key.into()))
+            } else {
+                check_key(meta, key, ty, &mut ans)
+            }
+        }
+    }
+
+    let hparams = match GGufHParams::from_meta(meta) {
+        Ok(hparams) => hparams,
+        Err(GGufHParamsError::Meta {
+            key,
+            error: GGufMetaError::OutOfRange | GGufMetaError::Read(_),
+        }) => {
+            ans.push(GGufArchIssue::InvalidKey(key));
+            return ans;
+        }
+        // 缺失和类型错误已经报告
+        Err(_) => return ans,
+    };
+    // clip 的张量使用另一套命名
+    let Some(specs) = tensor_specs(&hparams) else {
+        return ans;
+    };
+
+    // 列出架构需要的全部张量
+    let n_blocks = match &hparams {
+        GGufHParams::Llama(p) => p.transformer.block_count,
+        GGufHParams::Qwen2(p) => p.transformer.block_count,
+        GGufHParams::Gemma(p) => p.transformer.block_count,
+        GGufHParams::Phi3(p) => p.transformer.block_count,
+        GGufHParams::MiniCpm(p) => p.transformer.block_count,
+        GGufHParams::Mamba(p) => p.block_count,
+        GGufHParams::Bert(p) => p.block_count,
+        GGufHParams::Clip(p) => p.block_count,
+    };
+    let mut expected = IndexMap::new();
+    for &(kind, suffix, required) in &specs {
+        if kind.is_block() {
+            for i in 0..n_blocks {
+                expected.insert(
+                    GGufTensorName::new(Some(i), kind, suffix),
+                    (required, false),
+                );
+            }
+        } else {
+            expected.insert(GGufTensorName::new(None, kind, suffix), (required, false));
+        }
+    }
+
+    let tensors = tensors
+        .into_iter()
+        .map(|(name, shape)| (name.as_ref().to_string(), shape.as_ref().to_vec()))
+        .collect::<Vec<_>>();
+    let n_vocab = meta
+        .tokenizer_ggml_tokens()
+        .map(|tokens| tokens.len())
+        .ok()
+        .or_else(|| {
+            tensors
+                .iter()
+                .find(|(name, _)| name == "token_embd.weight")
+                .and_then(|(_, shape)| shape.last().map(|&n| n as usize))
+        })
+        .unwrap_or(0);
+
+    for (name, shape) in tensors {
+        let Some((parsed, (_, seen))) = name
+            .parse::<GGufTensorName>()
+            .ok()
+            .and_then(|parsed| expected.get_mut(&parsed).map(|v| (parsed, v)))
+        else {
+            ans.push(GGufArchIssue::UnexpectedTensor(name));
+            continue;
+        };
+        *seen = true;
+        if let Some(expected) = hparams.tensor_shape(&parsed, n_vocab) {
+            if expected != shape {
+                ans.push(GGufArchIssue::ShapeMismatch {
+                    name,
+                    expected,
+                    found: shape,
+                })
+            }
+        }
+    }
+    for (name, (required, seen)) in expected {
+        if required && !seen {
+            ans.push(GGufArchIssue::MissingTensor(name.to_string()))
+        }
+    }
+
+    ans
+}
+
+/// 元信息的类型，llama.cpp 读取时严格匹配类型
+#[derive(Clone, Copy)]
+enum KeyTy {
+    Scalar(Ty),
+    /// 标量或每层一个元素的数组，数组元素可以是 u32 或 i32
+    PerLayer,
+    Array(Ty),
+}
+
+const U32: KeyTy = KeyTy::Scalar(Ty::U32);
+const F32: KeyTy = KeyTy::Scalar(Ty::F32);
+const BOOL: KeyTy = KeyTy::Scalar(Ty::Bool);
+const STR: KeyTy = KeyTy::Scalar(Ty::String);
+const LAYER: KeyTy = KeyTy::PerLayer;
+
+fn check_key<M: GGufMetaMap>(meta: &M, key: &str, ty: KeyTy, ans: &mut Vec<GGufArchIssue>) {
+    let Some((found, val)) = meta.get(key) else {
+        return;
+    };
+    let (expected, elem) = match ty {
+        KeyTy::Scalar(ty) => (ty, None),
+        KeyTy::PerLayer => (Ty::U32, Some(Ty::U32)),
+        KeyTy::Array(ty) => (Ty::Array, Some(ty)),
+    };
+    if found == expected {
+        return;
+    }
+    match (found, elem) {
+        (Ty::Array, Some(elem)) => match meta.value_reader(val).read_arr_header() {
+            Ok((found, _)) if found == elem => {}
+            Ok((Ty::I32, _)) if matches!(ty, KeyTy::PerLayer) => {}
+            Ok((found, _)) => ans.push(GGufArchIssue::ArrTypeMismatch {
+                key: key.into(),
+                expected: elem,
+                found,
+            }),
+            Err(_) => ans.push(GGufArchIssue::InvalidKey(key.into())),
+        },
+        _ => ans.push(GGufArchIssue::KeyTypeMismatch {
+            key: key.into(),
+            expected,
+            found,
+        }),
+    }
+}
+
+const TOKENIZER_KEYS: &[(&str, KeyTy)] = &[
+    ("tokenizer.ggml.model", STR),
+    ("tokenizer.ggml.tokens", KeyTy::Array(Ty::String)),
+];
+
+type KeySpec = (&'static str, bool, KeyTy);
+
+const TRANSFORMER_KEYS: &[KeySpec] = &[
+    ("context_length", true, U32),
+    ("embedding_length", true, U32),
+    ("block_count", true, U32),
+    ("feed_forward_length", true, LAYER),
+    ("attention.head_count", true, LAYER),
+    ("attention.head_count_kv", false, LAYER),
+    ("attention.key_length", false, U32),
+    ("attention.value_length", false, U32),
+    ("attention.layer_norm_rms_epsilon", true, F32),
+    ("rope.dimension_count", false, U32),
+    ("rope.freq_base", false, F32),
+    ("rope.scaling.type", false, STR),
+    ("rope.scaling.factor", false, F32),
+    ("rope.scale_linear", false, F32),
+    ("rope.scaling.original_context_length", false, U32),
+    ("rope.scaling.finetuned", false, BOOL),
+];
+
+/// 各架构的元信息，键不含架构前缀
+fn arch_keys(arch: &str) -> Option<[&'static [KeySpec]; 2]> {
+    let keys: [&[KeySpec]; 2] = match arch {
+        "llama" => [
+            TRANSFORMER_KEYS,
+            &[
+                ("expert_count", false, U32),
+                ("expert_used_count", false, U32),
+            ],
+        ],
+        "qwen2" | "gemma" => [TRANSFORMER_KEYS, &[]],
+        "phi3" => [
+            TRANSFORMER_KEYS,
+            &[("attention.sliding_window", false, U32)],
+        ],
+        "minicpm" => [
+            TRANSFORMER_KEYS,
+            &[
+                ("embedding_scale", true, F32),
+                ("residual_scale", true, F32),
+                ("logit_scale", true, F32),
+            ],
+        ],
+        "mamba" => [
+            &[
+                ("context_length", true, U32),
+                ("embedding_length", true, U32),
+                ("block_count", true, U32),
+                ("ssm.conv_kernel", true, U32),
+                ("ssm.inner_size", true, U32),
+                ("ssm.state_size", true, U32),
+                ("ssm.time_step_rank", true, U32),
+                ("attention.layer_norm_rms_epsilon", true, F32),
+                ("ssm.dt_b_c_rms", false, BOOL),
+            ],
+            &[],
+        ],
+        "bert" => [
+            &[
+                ("context_length", true, U32),
+                ("embedding_length", true, U32),
+                ("block_count", true, U32),
+                ("feed_forward_length", true, U32),
+                ("attention.head_count", true, U32),
+                ("attention.layer_norm_epsilon", true, F32),
+                ("attention.causal", false, BOOL),
+                ("pooling_type", false, U32),
+            ],
+            &[],
+        ],
+        "clip" => [
+            &[
+                ("image_size", true, U32),
+                ("patch_size", true, U32),
+                ("embedding_length", true, U32),
+                ("feed_forward_length", true, U32),
+                ("block_count", true, U32),
+                ("attention.head_count", true, U32),
+                ("attention.layer_norm_epsilon", true, F32),
+                ("projection_dim", false, U32),
+            ],
+            &[],
+        ],
+        _ => return None,
+    };
+    Some(keys)
+}
+
+type TensorSpec = (K, Option<GGufTensorSuffix>, bool);
+
+const W: Option<GGufTensorSuffix> = Some(GGufTensorSuffix::Weight);
+const B: Option<GGufTensorSuffix> = Some(GGufTensorSuffix::Bias);
+
+/// 各架构的张量，参考 llama.cpp 的 `llm_load_tensors`
+fn tensor_specs(hparams: &GGufHParams) -> Option<Vec<TensorSpec>> {
+    let mut ans = Vec::new();
+    match hparams {
+        GGufHParams::Llama(p) => {
+            add(&mut ans, &[K::TokenEmbd, K::OutputNorm], W, true);
+            add(
+                &mut ans,
+                &[K::AttnNorm, K::AttnQ, K::AttnK, K::AttnV, K::AttnOutput],
+                W,
+                true,
+            );
+            add(&mut ans, &[K::FfnNorm], W, true);
+            if p.expert_count > 0 {
+                add(
+                    &mut ans,
+                    &[K::FfnGateInp, K::FfnGateExps, K::FfnDownExps, K::FfnUpExps],
+                    W,
+                    true,
+                );
+            } else {
+                add(&mut ans, &[K::FfnGate, K::FfnDown, K::FfnUp], W, true);
+                add(&mut ans, &[K::FfnGate, K::FfnDown, K::FfnUp], B, false);
+            }
+            add(&mut ans, &[K::Output, K::RopeFreqs], W, false);
+            add(
+                &mut ans,
+                &[K::AttnQ, K::AttnK, K::AttnV, K::AttnOutput],
+                B,
+                false,
+            );
+        }
+        GGufHParams::MiniCpm(_) => {
+            add(&mut ans, &[K::TokenEmbd, K::OutputNorm], W, true);
+            add(
+                &mut ans,
+                &[K::AttnNorm, K::AttnQ, K::AttnK, K::AttnV, K::AttnOutput],
+                W,
+                true,
+            );
+            add(
+                &mut ans,
+                &[K::FfnNorm, K::FfnGate, K::FfnDown, K::FfnUp],
+                W,
+                true,
+            );
+            add(&mut ans, &[K::Output, K::RopeFreqs], W, false);
+        }
+        GGufHParams::Qwen2(_) => {
+            add(&mut ans, &[K::TokenEmbd, K::OutputNorm], W, true);
+            add(
+                &mut ans,
+                &[K::AttnNorm, K::AttnQ, K::AttnK, K::AttnV, K::AttnOutput],
+                W,
+                true,
+            );
+            add(&mut ans, &[K::AttnQ, K::AttnK, K::AttnV], B, true);
+            add(
+                &mut ans,
+                &[K::FfnNorm, K::FfnGate, K::FfnDown, K::FfnUp],
+                W,
+                true,
+            );
+            add(&mut ans, &[K::Output], W, false);
+        }
+        GGufHParams::Gemma(_) => {
+            add(&mut ans, &[K::TokenEmbd, K::OutputNorm], W, true);
+            add(
+                &mut ans,
+                &[K::AttnNorm, K::AttnQ, K::AttnK, K::AttnV, K::AttnOutput],
+                W,
+                true,
+            );
+            add(
+                &mut ans,
+                &[K::FfnNorm, K::FfnGate, K::FfnDown, K::FfnUp],
+                W,
+                true,
+            );
+        }
+        GGufHParams::Phi3(_) => {
+            add(&mut ans, &[K::TokenEmbd, K::OutputNorm, K::Output], W, true);
+            add(&mut ans, &[K::AttnNorm, K::AttnQkv, K::AttnOutput], W, true);
+            add(&mut ans, &[K::FfnNorm, K::FfnDown, K::FfnUp], W, true);
+            add(
+                &mut ans,
+                &[K::RopeFactorsLong, K::RopeFactorsShort],
+                W,
+                false,
+            );
+        }
+        GGufHParams::Mamba(_) => {
+            add(&mut ans, &[K::TokenEmbd, K::OutputNorm], W, true);
+            add(
+                &mut ans,
+                &[
+                    K::AttnNorm,
+                    K::SsmIn,
+                    K::SsmConv1d,
+                    K::SsmX,
+                    K::SsmDt,
+                    K::SsmOut,
+                ],
+                W,
+                true,
+            );
+            add(&mut ans, &[K::SsmConv1d, K::SsmDt], B, true);
+            add(&mut ans, &[K::SsmA, K::SsmD], None, true);
+            add(&mut ans, &[K::Output], W, false);
+        }
+        GGufHParams::Bert(_) => {
+            add(
+                &mut ans,
+                &[K::TokenEmbd, K::TokenTypes, K::PosEmbd],
+                W,
+                true,
+            );
+            const NORMS: &[K] = &[K::TokenEmbdNorm, K::AttnOutputNorm, K::LayerOutputNorm];
+            const LINEARS: &[K] = &[
+                K::AttnQ,
+                K::AttnK,
+                K::AttnV,
+                K::AttnOutput,
+                K::FfnUp,
+                K::FfnDown,
+            ];
+            add(&mut ans, NORMS, W, true);
+            add(&mut ans, NORMS, B, true);
+            add(&mut ans, LINEARS, W, true);
+            add(&mut ans, LINEARS, B, true);
+            add(&mut ans, &[K::Cls, K::ClsOutput], W, false);
+            add(&mut ans, &[K::Cls, K::ClsOutput], B, false);
+        }
+        GGufHParams::Clip(_) => return None,
+    }
+    Some(ans)
+}
+
+fn add(ans: &mut Vec<TensorSpec>, kinds: &[K], suffix: Option<GGufTensorSuffix>, required: bool) {
+    ans.extend(kinds.iter().map(|&k| (k, suffix, required)))
+}
+
+#[test]
+fn test_check_arch() {
+    use crate::{GGufDocument, GGufMetaValue as V};
+
+    let mut doc = GGufDocument::new();
+    let mut set = |k: &str, v: V| {
+        doc.insert_meta_kv(k.to_string(), v.to_meta_buf().unwrap());
+    };
+    set("general.architecture", V::String("llama".into()));
+    set("llama.context_length", V::U64(2048));
+    set("llama.embedding_length", V::U32(64));
+    set("llama.block_count", V::U32(1));
+    set(
+        "llama.feed_forward_length",
+        V::Array(Ty::I32, vec![V::I32(128)]),
+    );
+    set(
+        "llama.attention.head_count",
+        V::Array(Ty::U16, vec![V::U16(4)]),
+    );
+    set("llama.attention.layer_norm_rms_epsilon", V::F32(1e-5));
+    set("tokenizer.ggml.model", V::String("llama".into()));
+    set(
+        "tokenizer.ggml.tokens",
+        V::Array(Ty::String, vec![V::String("a".into()); 10]),
+    );
+
+    let tensors: [(&str, &[u64]); 10] = [
+        ("token_embd.weight", &[64, 10]),
+        ("output_norm.weight", &[64]),
+        ("blk.0.attn_norm.weight", &[64]),
+        ("blk.0.attn_qkv.weight", &[64, 192]),
+        ("blk.0.attn_q.weight", &[64, 32]),
+        ("blk.0.attn_k.weight", &[64, 64]),
+        ("blk.0.attn_v.weight", &[64, 64]),
+        ("blk.0.attn_output.weight", &[64, 64]),
+        ("blk.0.ffn_norm.weight", &[64]),
+        ("blk.0.ffn_gate.weight", &[64, 128]),
+    ];
+    let issues = doc.check_arch(tensors);
+    assert_eq!(
+        issues,
+        [
+            GGufArchIssue::KeyTypeMismatch {
+                key: "llama.context_length".into(),
+                expected: Ty::U32,
+                found: Ty::U64,
+            },
+            GGufArchIssue::ArrTypeMismatch {
+                key: "llama.attention.head_count".into(),
+                expected: Ty::U32,
+                found: Ty::U16,
+            },
+            GGufArchIssue::UnexpectedTensor("blk.0.attn_qkv.weight".into()),
+            GGufArchIssue::ShapeMismatch {
+                name: "blk.0.attn_q.weight".into(),
+                expected: vec![64, 64],
+                found: vec![64, 32],
+            },
+            GGufArchIssue::MissingTensor("blk.0.ffn_down.weight".into()),
+            GGufArchIssue::MissingTensor("blk.0.ffn_up.weight".into()),
+        ]
+    );
+    assert_eq!(
+        issues[0].to_string(),
+        "meta key llama.context_length is u64, expected u32"
+    );
+
+    doc.insert_meta_kv(
+        "general.architecture",
+        V::String("gpt2".into()).to_meta_buf().unwrap(),
+    );
+    assert_eq!(
+        doc.check_arch(tensors),
+        [GGufArchIssue::UnsupportedArchitecture("gpt2".into())]
+    );
+}
@@ -0,0 +1,209 @@
+use crate::{
+    stream::{parse, Parsed, Source},
+    GGufError, GGufFileHeader, GGufMetaBuf, GGufMetaDataValueType as Ty, GGufMetaMap,
+    GGufTensorInfo,
+};
+use indexmap::IndexMap;
+use std::{io, ops::Range};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+/// Async counterpart of [GGufStream](crate::GGufStream), parsing from an [AsyncRead] source.
+///
+/// Tensor data is not read during parsing, it can be fetched on demand if the source is also [AsyncSeek].
+pub struct GGufAsyncStream<R> {
+    reader: R,
+    /// 已从数据源读取的字节数
+    pos: u64,
+    /// The header in native byte order.
+    pub header: GGufFileHeader,
+    /// Whether the file is in native byte order.
+    pub native_endian: bool,
+    pub alignment: usize,
+    pub meta_kvs: IndexMap<String, GGufMetaBuf<'static>>,
+    pub tensors: IndexMap<String, GGufTensorInfo>,
+    /// Offset of tensor data from the start of the file, tensor offsets are relative to it.
+    pub data_offset: u64,
+}
+
+impl<R> GGufMetaMap for GGufAsyncStream<R> {
+    fn get(&self, key: &str) -> Option<(Ty, &[u8])> {
+        self.meta_kvs.get(key).map(|v| (v.ty, &*v.value))
+    }
+}
+
+impl<R: AsyncRead + Unpin> GGufAsyncStream<R> {
+    /// Parses the header, metadata and tensor infos from the current position of `reader`,
+    /// leaving it at the start of tensor data.
+    pub async fn new(reader: R) -> Result<Self, GGufError> {
+        let mut source = AsyncSource(reader);
+        let Parsed {
+            pos,
+            header,
+            native_endian,
+            alignment,
+            meta_kvs,
+            tensors,
+        } = parse(&mut source).await?;
+        Ok(Self {
+            reader: source.0,
+            pos,
+            header,
+            native_endian,
+            alignment,
+            meta_kvs,
+            tensors,
+            data_offset: pos,
+        })
+    }
+
+    /// Returns the source, which is left at the start of tensor data if no tensor has been fetched.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> GGufAsyncStream<R> {
+    /// Reads the data of a tensor and converts it into native byte order.
+    pub async fn read_tensor_data(&mut self, name: &str) -> io::Result<Vec<u8>> {
+        let Some(info) = self.tensors.get(name) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("tensor {name} not found"),
+            ));
+        };
+        let ty = info.ty();
+        let range = info.offset() as u64..(info.offset() + info.nbytes()) as u64;
+
+        let mut data = self.read_data(range).await?;
+        if !self.native_endian {
+            ty.swap_bytes(&mut data)?
+        }
+        Ok(data)
+    }
+
+    /// Reads a range of tensor data as is, the range is relative to [GGufAsyncStream::data_offset].
+    pub async fn read_data(&mut self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let pos = self.data_offset + range.start;
+        let mut data = vec![0; (range.end - range.start) as _];
+
+        // 只知道相对位置，所以从当前位置出发移动
+        self.reader
+            .seek(io::SeekFrom::Current(pos as i64 - self.pos as i64))
+            .await?;
+        self.pos = pos;
+        self.reader.read_exact(&mut data).await?;
+        self.pos += data.len() as u64;
+
+        Ok(data)
+    }
+}
+
+struct AsyncSource<R>(R);
+
+impl<R: AsyncRead + Unpin> Source for AsyncSource<R> {
+    async fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.0.read_exact(buf).await.map(|_| ())
+    }
+
+    async fn read_at_most(&mut self, len: u64, buf: &mut Vec<u8>) -> io::Result<u64> {
+        (&mut self.0)
+            .take(len)
+            .read_to_end(buf)
+            .await
+            .map(|n| n as _)
+    }
+
+    async fn skip(&mut self, len: u64) -> io::Result<u64> {
+        tokio::io::copy(&mut (&mut self.0).take(len), &mut tokio::io::sink()).await
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_async_stream() {
+    use crate::{
+        DataFuture, GGmlType, GGufDocument, GGufMetaMap, GGufMetaMapExt, GGufReadError,
+        GGufTensorBuf, GGufWriter,
+    };
+    use std::io::Cursor;
+
+    let mut doc = GGufDocument::new();
+    doc.alignment = 64;
+    doc.insert_meta_kv("general.architecture", GGufMetaBuf::string("llama"));
+    // 嵌套数组 [[1u8, 2u8], ["x"]]
+    let mut nested = Vec::new();
+    let mut w = GGufWriter::new(&mut nested);
+    w.write(&[Ty::Array]).unwrap();
+    w.write(&[2u64]).unwrap();
+    w.write(&[Ty::U8]).unwrap();
+    w.write(&[2u64]).unwrap();
+    w.write(&[1u8, 2]).unwrap();
+    w.write(&[Ty::String]).unwrap();
+    w.write(&[1u64]).unwrap();
+    w.write_str("x").unwrap();
+    drop(w);
+    doc.insert_meta_kv(
+        "test.nested",
+        GGufMetaBuf {
+            ty: Ty::Array,
+            value: nested.clone().into(),
+        },
+    );
+    let data = (0..8)
+        .flat_map(|x| (x as f32).to_ne_bytes())
+        .collect::<Vec<_>>();
+    doc.insert_tensor(
+        "a",
+        GGufTensorBuf {
+            ty: GGmlType::F32,
+            shape: vec![4, 2],
+            data: data.clone().into(),
+        },
+    );
+
+    for native_endian in [true, false] {
+        let mut file = Vec::new();
+        doc.write_with_endian(&mut file, native_endian).unwrap();
+
+        let mut stream = GGufAsyncStream::new(Cursor::new(&file)).await.unwrap();
+        assert_eq!(stream.native_endian, native_endian);
+        assert_eq!(stream.alignment, 64);
+        assert_eq!(stream.general_architecture().unwrap(), "llama");
+        assert_eq!(stream.get("test.nested").unwrap(), (Ty::Array, &*nested));
+        assert_eq!(stream.tensors["a"].shape(), [4, 2]);
+        assert_eq!(stream.data_offset % 64, 0);
+        assert_eq!(
+            stream.read_tensor_data("a").await.unwrap(),
+            doc.tensors["a"].data.get()
+        );
+        if native_endian {
+            assert_eq!(stream.read_data(4..12).await.unwrap(), data[4..12]);
+        }
+
+        // 解析不读取张量数据
+        let stream = GGufAsyncStream::new(Cursor::new(&file)).await.unwrap();
+        let offset = stream.data_offset;
+        assert_eq!(stream.into_inner().position(), offset);
+    }
+
+    // 截断的文件
+    let mut file = Vec::new();
+    doc.write(&mut file).unwrap();
+    assert!(matches!(
+        GGufAsyncStream::new(&file[..100]).await,
+        Err(GGufError::Reading(GGufReadError::Eos))
+    ));
+
+    // 越界的类型标签
+    let mut file = Vec::new();
+    let mut w = GGufWriter::new(&mut file);
+    w.write_header(GGufFileHeader::new(3, 0, 1)).unwrap();
+    w.write_str("x").unwrap();
+    w.write(&[99u32]).unwrap();
+    drop(w);
+    assert!(matches!(
+        GGufAsyncStream::new(&*file).await,
+        Err(GGufError::InvalidMetaType(99))
+    ));
+}
@@ -0,0 +1,56 @@
+use super::{GGufDocument, GGufMetaBuf, GGufTensorBuf, GGufTensorData};
+use crate::{metadata::is_layout_key, GGuf, GGufError};
+
+impl<'a> GGufDocument<'a> {
+    /// Loads a document from a parsed file, borrowing metadata values and tensor data wherever possible.
+    pub fn load(gguf: GGuf<'a>) -> Result<Self, GGufError> {
+        let mut ans = Self {
+            alignment: gguf.alignment,
+            ..Default::default()
+        };
+        ans.merge(gguf)?;
+        Ok(ans)
+    }
+
+    /// Merges the contents of a parsed file into this document, the larger alignment is kept.
+    ///
+    /// `general.alignment`, `general.padding` and the shard bookkeeping keys `split.*`
+    /// describe the file layout instead of the model, so they are not copied.
+    pub fn merge(&mut self, gguf: GGuf<'a>) -> Result<(), GGufError> {
+        self.alignment = self.alignment.max(gguf.alignment);
+
+        for (&k, kv) in &gguf.meta_kvs {
+            if is_layout_key(k) {
+                continue;
+            }
+            let value = if gguf.native_endian && gguf.header.version == 3 {
+                GGufMetaBuf {
+                    ty: kv.ty(),
+                    value: kv.value_bytes().into(),
+                }
+            } else {
+                GGufMetaBuf::transcode(kv.ty(), kv.value_reader(), true)
+            };
+            if self.meta_kvs.insert(k.into(), value).is_some() {
+                return Err(GGufError::DuplicateMetaKey(k.into()));
+            }
+        }
+
+        for &name in gguf.tensors.keys() {
+            let view = gguf.tensor(name).unwrap();
+            let mut tensor = GGufTensorBuf {
+                ty: view.ty(),
+                shape: view.shape().to_vec(),
+                data: GGufTensorData::Borrowed(view.data()),
+            };
+            if !gguf.native_endian {
+                tensor.swap_bytes().map_err(GGufError::Io)?
+            }
+            if self.tensors.insert(name.into(), tensor).is_some() {
+                return Err(GGufError::DuplicateTensorName(name.into()));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,279 @@
+mod load;
+mod transcode;
+mod write;
+
+use crate::{
+    DataFuture, GGmlType, GGufMetaDataValueType, GGufMetaMap, GGufWriter, DEFAULT_ALIGNMENT,
+};
+use indexmap::IndexMap;
+use std::{
+    borrow::Cow,
+    ops::Deref,
+    sync::{Arc, LazyLock},
+};
+
+/// An owned, editable GGUF document.
+///
+/// Metadata values and tensor data are always kept in native byte order and in the format of the latest GGUF version.
+/// The alignment is kept in [GGufDocument::alignment] rather than as a metadata entry.
+#[derive(Clone)]
+pub struct GGufDocument<'a> {
+    pub alignment: usize,
+    pub meta_kvs: IndexMap<Cow<'a, str>, GGufMetaBuf<'a>>,
+    pub tensors: IndexMap<Cow<'a, str>, GGufTensorBuf<'a>>,
+}
+
+/// Type and encoded bytes of a metadata value.
+#[derive(Clone, Debug)]
+pub struct GGufMetaBuf<'a> {
+    pub ty: GGufMetaDataValueType,
+    pub value: Cow<'a, [u8]>,
+}
+
+/// Type, shape and data of a tensor.
+#[derive(Clone)]
+pub struct GGufTensorBuf<'a> {
+    pub ty: GGmlType,
+    pub shape: Vec<u64>,
+    pub data: GGufTensorData<'a>,
+}
+
+/// Tensor data, which is borrowed from a mapped file, owned, or computed on first access.
+#[derive(Clone)]
+pub enum GGufTensorData<'a> {
+    Borrowed(&'a [u8]),
+    Owned(Arc<[u8]>),
+    Lazy(Arc<dyn DataFuture + Send + Sync + 'a>),
+}
+
+impl Default for GGufDocument<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            alignment: DEFAULT_ALIGNMENT,
+            meta_kvs: Default::default(),
+            tensors: Default::default(),
+        }
+    }
+}
+
+impl GGufMetaMap for GGufDocument<'_> {
+    fn get(&self, key: &str) -> Option<(GGufMetaDataValueType, &[u8])> {
+        self.meta_kvs.get(key).map(|v| (v.ty, &*v.value))
+    }
+}
+
+impl<'a> GGufDocument<'a> {
+    /// Creates an empty document with the default alignment.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn insert_meta_kv(
+        &mut self,
+        key: impl Into<Cow<'a, str>>,
+        value: GGufMetaBuf<'a>,
+    ) -> Option<GGufMetaBuf<'a>> {
+        self.meta_kvs.insert(key.into(), value)
+    }
+
+    /// Removes a metadata entry, keeping the order of the others.
+    #[inline]
+    pub fn remove_meta_kv(&mut self, key: &str) -> Option<GGufMetaBuf<'a>> {
+        self.meta_kvs.shift_remove(key)
+    }
+
+    /// Renames a metadata entry in place.
+    ///
+    /// Returns false and leaves the document unchanged if `key` does not exist or `new` is taken by another entry.
+    #[inline]
+    pub fn rename_meta_kv(&mut self, key: &str, new: impl Into<Cow<'a, str>>) -> bool {
+        rename(&mut self.meta_kvs, key, new.into())
+    }
+
+    #[inline]
+    pub fn insert_tensor(
+        &mut self,
+        name: impl Into<Cow<'a, str>>,
+        tensor: GGufTensorBuf<'a>,
+    ) -> Option<GGufTensorBuf<'a>> {
+        self.tensors.insert(name.into(), tensor)
+    }
+
+    /// Removes a tensor, keeping the order of the others.
+    #[inline]
+    pub fn remove_tensor(&mut self, name: &str) -> Option<GGufTensorBuf<'a>> {
+        self.tensors.shift_remove(name)
+    }
+
+    /// Renames a tensor in place.
+    ///
+    /// Returns false and leaves the document unchanged if `name` does not exist or `new` is taken by another tensor.
+    #[inline]
+    pub fn rename_tensor(&mut self, name: &str, new: impl Into<Cow<'a, str>>) -> bool {
+        rename(&mut self.tensors, name, new.into())
+    }
+}
+
+fn rename<'a, V>(map: &mut IndexMap<Cow<'a, str>, V>, key: &str, new: Cow<'a, str>) -> bool {
+    if key == new {
+        return map.contains_key(key);
+    }
+    if map.contains_key(&*new) {
+        return false;
+    }
+    let Some((i, _, v)) = map.shift_remove_full(key) else {
+        return false;
+    };
+    let (j, _) = map.insert_full(new, v);
+    map.move_index(j, i);
+    true
+}
+
+impl GGufMetaBuf<'_> {
+    pub fn string(s: &str) -> Self {
+        Self {
+            ty: GGufMetaDataValueType::String,
+            value: {
+                let mut vec = Vec::with_capacity(s.len() + size_of::<u64>());
+                GGufWriter::new(&mut vec).write_str(s).unwrap();
+                vec.into()
+            },
+        }
+    }
+}
+
+impl GGufTensorBuf<'_> {
+    #[inline]
+    pub fn nbytes(&self) -> usize {
+        self.ty.size().elements_to_bytes(&self.shape)
+    }
+}
+
+impl<'a> GGufTensorData<'a> {
+    /// Creates tensor data computed by `f` on first access.
+    pub fn lazy<T, F>(f: F) -> Self
+    where
+        T: Deref<Target = [u8]> + Send + Sync + 'a,
+        F: FnOnce() -> T + Send + Sync + 'a,
+    {
+        Self::Lazy(Arc::new(Lazy(LazyLock::new(f))))
+    }
+}
+
+impl From<Vec<u8>> for GGufTensorData<'_> {
+    #[inline]
+    fn from(value: Vec<u8>) -> Self {
+        Self::Owned(value.into())
+    }
+}
+
+impl<'a> From<&'a [u8]> for GGufTensorData<'a> {
+    #[inline]
+    fn from(value: &'a [u8]) -> Self {
+        Self::Borrowed(value)
+    }
+}
+
+impl DataFuture for GGufTensorData<'_> {
+    #[inline]
+    fn get(&self) -> &[u8] {
+        match self {
+            Self::Borrowed(data) => data,
+            Self::Owned(data) => data,
+            Self::Lazy(data) => data.get(),
+        }
+    }
+}
+
+struct Lazy<T, F>(LazyLock<T, F>);
+
+impl<T: Deref<Target = [u8]>, F: FnOnce() -> T> DataFuture for Lazy<T, F> {
+    #[inline]
+    fn get(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[test]
+fn test_edit() {
+    use crate::{GGuf, GGufMetaMapExt};
+
+    let mut doc = GGufDocument::new();
+    doc.insert_meta_kv("general.architecture", GGufMetaBuf::string("llama"));
+    doc.insert_meta_kv("general.name", GGufMetaBuf::string("tiny"));
+    doc.insert_meta_kv("general.author", GGufMetaBuf::string("nobody"));
+    let data = (0..8)
+        .flat_map(|x| (x as f32).to_ne_bytes())
+        .collect::<Vec<_>>();
+    doc.insert_tensor(
+        "a",
+        GGufTensorBuf {
+            ty: GGmlType::F32,
+            shape: vec![4, 2],
+            data: data.clone().into(),
+        },
+    );
+    doc.insert_tensor(
+        "b",
+        GGufTensorBuf {
+            ty: GGmlType::F32,
+            shape: vec![8],
+            data: GGufTensorData::lazy(move || data),
+        },
+    );
+
+    assert!(doc.rename_meta_kv("general.name", "general.basename"));
+    assert!(!doc.rename_meta_kv("general.basename", "general.author"));
+    assert!(!doc.rename_tensor("c", "d"));
+    assert!(doc.rename_tensor("a", "c"));
+    assert!(doc.remove_meta_kv("general.author").is_some());
+    assert_eq!(
+        doc.meta_kvs.keys().collect::<Vec<_>>(),
+        ["general.architecture", "general.basename"]
+    );
+    assert_eq!(doc.tensors.keys().collect::<Vec<_>>(), ["c", "b"]);
+
+    let mut file = Vec::new();
+    let len = doc.write(&mut file).unwrap();
+    assert_eq!(len, file.len());
+
+    let gguf = GGuf::new(&file).unwrap();
+    assert_eq!(gguf.general_architecture().unwrap(), "llama");
+    assert_eq!(gguf.get_str("general.basename").unwrap(), "tiny");
+    assert_eq!(gguf.tensors["b"].to_info().offset(), 32);
+
+    let mut doc = GGufDocument::load(gguf).unwrap();
+    assert_eq!(doc.get_str("general.basename").unwrap(), "tiny");
+
+    // 文件布局相关的键由写入器生成，文档中的同名项不会重复写入
+    let u32_buf = |x: u32| GGufMetaBuf {
+        ty: GGufMetaDataValueType::U32,
+        value: x.to_ne_bytes().to_vec().into(),
+    };
+    doc.insert_meta_kv(crate::GENERAL_ALIGNMENT, u32_buf(64));
+    doc.insert_meta_kv(crate::SPLIT_NO, u32_buf(7));
+    for shard in [None, Some(0), Some(1)] {
+        let mut file = Vec::new();
+        match shard {
+            None => doc.write(&mut file),
+            Some(no) => doc.write_shard(&mut file, 0..2, no, 2, true),
+        }
+        .unwrap();
+        let gguf = GGuf::new(&file).unwrap();
+        assert_eq!(gguf.alignment, 32);
+        if let Some(no) = shard {
+            assert_eq!(gguf.get_usize(crate::SPLIT_NO).unwrap(), no);
+        }
+    }
+    assert_eq!(
+        doc.write_shard(&mut Vec::new(), 0..0, 0, 1 << 16, true)
+            .unwrap_err()
+            .kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+    assert_eq!(doc.tensors["c"].shape, [4, 2]);
+    assert_eq!(doc.tensors["c"].data.get(), doc.tensors["b"].data.get());
+}
@@ -0,0 +1,81 @@
+use super::{GGufMetaBuf, GGufTensorBuf, GGufTensorData};
+use crate::{DataFuture, GGufMetaDataValueType as Ty, GGufReadError, GGufReader, GGufWriter};
+use std::io::{self, Write};
+
+impl GGufMetaBuf<'_> {
+    /// Reverses the byte order of the value, `native_endian` tells whether the value is currently in native byte order.
+    pub fn swap_bytes(&self, native_endian: bool) -> Self {
+        let reader = GGufReader::with_endian(&self.value, native_endian);
+        Self::transcode(self.ty, reader, !native_endian)
+    }
+
+    /// Reads a value in the format of `reader` and encodes it again in the latest format and the given byte order.
+    pub fn transcode(ty: Ty, mut reader: GGufReader, native_endian: bool) -> Self {
+        let mut value = Vec::with_capacity(reader.remaining().len());
+        let mut writer = GGufWriter::with_endian(&mut value, native_endian);
+        transcode_value(&mut reader, &mut writer, ty, 1).unwrap();
+        drop(writer);
+        Self {
+            ty,
+            value: value.into(),
+        }
+    }
+}
+
+impl GGufTensorBuf<'_> {
+    /// Reverses the byte order of the tensor data lazily.
+    ///
+    /// Fails if [GGmlType::swap_bytes](crate::GGmlType::swap_bytes) does not support the type of the tensor.
+    pub fn swap_bytes(&mut self) -> io::Result<()> {
+        let ty = self.ty;
+        if !ty.can_swap_bytes() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("byte swapping {ty:?} is not supported"),
+            ));
+        }
+        let data = self.data.clone();
+        self.data = GGufTensorData::lazy(move || {
+            let mut ans = data.get().to_vec();
+            ty.swap_bytes(&mut ans).unwrap();
+            ans
+        });
+        Ok(())
+    }
+}
+
+fn transcode_value(
+    reader: &mut GGufReader,
+    writer: &mut GGufWriter<impl Write>,
+    ty: Ty,
+    len: usize,
+) -> Result<(), GGufReadError> {
+    macro_rules! swap {
+        ($ty:ty) => {
+            for _ in 0..len {
+                writer.write(&[reader.read::<$ty>()?]).unwrap()
+            }
+        };
+    }
+
+    match ty {
+        Ty::U8 | Ty::I8 | Ty::Bool => swap!(u8),
+        Ty::U16 | Ty::I16 => swap!(u16),
+        Ty::U32 | Ty::I32 | Ty::F32 => swap!(u32),
+        Ty::U64 | Ty::I64 | Ty::F64 => swap!(u64),
+        Ty::String => {
+            for _ in 0..len {
+                writer.write_str(reader.read_str()?).unwrap()
+            }
+        }
+        Ty::Array => {
+            for _ in 0..len {
+                let (ty, len) = reader.read_arr_header()?;
+                writer.write(&[ty]).unwrap();
+                writer.write(&[len as u64]).unwrap();
+                transcode_value(reader, writer, ty, len)?
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use super::{GGufDocument, GGufMetaBuf, GGufTensorData};
+use crate::{
+    metadata::is_layout_key, GGufFileHeader, GGufFileWriter, GGufMetaDataValueType as Ty,
+    GGufWriter, SPLIT_COUNT, SPLIT_NO, SPLIT_TENSORS_COUNT,
+};
+use std::{
+    io::{Error, ErrorKind, Result, Write},
+    ops::Range,
+};
+
+impl GGufDocument<'_> {
+    /// Writes the document as a GGUF file of the latest version in native byte order,
+    /// returns the number of bytes written.
+    ///
+    /// `general.alignment` is written from [GGufDocument::alignment], and entries of [GGufDocument::meta_kvs]
+    /// describing the file layout (`general.alignment`, `general.padding` and `split.*`) are skipped.
+    #[inline]
+    pub fn write<T: Write>(&self, writer: T) -> Result<usize> {
+        self.write_with_endian(writer, true)
+    }
+
+    /// Writes the document as a GGUF file of the latest version,
+    /// in native byte order if `native_endian` is true, or in the opposite byte order otherwise.
+    #[inline]
+    pub fn write_with_endian<T: Write>(&self, writer: T, native_endian: bool) -> Result<usize> {
+        self.write_impl(writer, 0..self.tensors.len(), None, native_endian)
+    }
+
+    /// Writes the tensors in `tensors` as shard `no` (zero-based) of `count` shards,
+    /// in native byte order if `native_endian` is true, or in the opposite byte order otherwise.
+    ///
+    /// Metadata is written to the first shard only, and every shard carries
+    /// `split.no`, `split.count` and `split.tensors.count` as llama.cpp expects.
+    ///
+    /// Like [GGufDocument::write], entries of [GGufDocument::meta_kvs] describing the file layout are skipped.
+    /// Fails with [ErrorKind::InvalidInput] if `count` does not fit in the `u16` of `split.count`.
+    #[inline]
+    pub fn write_shard<T: Write>(
+        &self,
+        writer: T,
+        tensors: Range<usize>,
+        no: usize,
+        count: usize,
+        native_endian: bool,
+    ) -> Result<usize> {
+        assert!(no < count);
+        self.write_impl(writer, tensors, Some((no, count)), native_endian)
+    }
+
+    /// Number of metadata entries written by [GGufDocument::write], including `general.alignment`.
+    #[inline]
+    pub fn meta_kv_count(&self) -> usize {
+        self.model_meta_kvs().count() + 1
+    }
+
+    /// Number of metadata entries written to shard `no`, including `general.alignment` and the `split.*` keys.
+    #[inline]
+    pub fn shard_meta_kv_count(&self, no: usize) -> usize {
+        if no == 0 {
+            self.model_meta_kvs().count() + 4
+        } else {
+            4
+        }
+    }
+
+    fn write_impl<T: Write>(
+        &self,
+        writer: T,
+        tensors: Range<usize>,
+        split: Option<(usize, usize)>,
+        native_endian: bool,
+    ) -> Result<usize> {
+        let n_meta_kvs = match split {
+            Some((no, _)) => self.shard_meta_kv_count(no),
+            None => self.meta_kv_count(),
+        };
+        // split.no 与 split.count 以 u16 写入
+        let split = match split {
+            Some((no, count)) => match u16::try_from(count) {
+                Ok(count) => Some((no as u16, count)),
+                Err(_) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("shard count {count} exceeds {}", u16::MAX),
+                    ))
+                }
+            },
+            None => None,
+        };
+        let header = GGufFileHeader::new(3, tensors.len() as _, n_meta_kvs as _);
+        let mut writer = GGufFileWriter::with_endian(writer, header, native_endian)?;
+        writer.write_alignment(self.alignment)?;
+        if let Some((no, count)) = split {
+            let n_tensors = self.tensors.len() as i32;
+            writer.write_meta_kv(SPLIT_NO, Ty::U16, &encode(no, native_endian))?;
+            writer.write_meta_kv(SPLIT_COUNT, Ty::U16, &encode(count, native_endian))?;
+            writer.write_meta_kv(
+                SPLIT_TENSORS_COUNT,
+                Ty::I32,
+                &encode(n_tensors, native_endian),
+            )?;
+        }
+        if split.is_none_or(|(no, _)| no == 0) {
+            for (k, v) in self.model_meta_kvs() {
+                if native_endian {
+                    writer.write_meta_kv(k, v.ty, &v.value)?
+                } else {
+                    writer.write_meta_kv(k, v.ty, &v.swap_bytes(true).value)?
+                }
+            }
+        }
+
+        let mut writer = writer.finish::<GGufTensorData>();
+        for (name, tensor) in &self.tensors[tensors] {
+            let data = if native_endian {
+                tensor.data.clone()
+            } else {
+                let mut tensor = tensor.clone();
+                tensor.swap_bytes()?;
+                tensor.data
+            };
+            writer.write_tensor(name, tensor.ty, &tensor.shape, data)?
+        }
+        writer.finish()
+    }
+
+    /// 文档中除文件布局以外的元信息。
+    fn model_meta_kvs(&self) -> impl Iterator<Item = (&str, &GGufMetaBuf<'_>)> {
+        self.meta_kvs
+            .iter()
+            .map(|(k, v)| (&**k, v))
+            .filter(|(k, _)| !is_layout_key(k))
+    }
+}
+
+fn encode<T: Copy + 'static>(val: T, native_endian: bool) -> Vec<u8> {
+    let mut ans = Vec::with_capacity(size_of::<T>());
+    GGufWriter::with_endian(&mut ans, native_endian)
+        .write(&[val])
+        .unwrap();
+    ans
+}
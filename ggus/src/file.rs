@@ -3,10 +3,12 @@
     GGufTensorMeta, DEFAULT_ALIGNMENT, GENERAL_ALIGNMENT,
 };
 use indexmap::IndexMap;
-use std::{error::Error, fmt};
+use std::{error::Error, fmt, io};
 
 pub struct GGuf<'a> {
     pub header: GGufFileHeader,
+    /// Whether the file is in native byte order, the header is always decoded into native byte order.
+    pub native_endian: bool,
     pub alignment: usize,
     pub meta_kvs: IndexMap<&'a str, GGufMetaKV<'a>>,
     pub tensors: IndexMap<&'a str, GGufTensorMeta<'a>>,
@@ -16,24 +18,38 @@ pub struct GGuf<'a> {
 #[derive(Debug)]
 pub enum GGufError {
     Reading(GGufReadError),
+    Io(io::Error),
     MagicMismatch,
+    #[deprecated(note = "byte-swapped files are supported, this error is no longer returned")]
     EndianNotSupport,
     VersionNotSupport,
     AlignmentTypeMismatch(GGufMetaDataValueType),
+    /// The alignment is zero or not a power of two.
+    InvalidAlignment(u64),
+    InvalidMetaType(u32),
+    InvalidTensorType(u32),
     DuplicateMetaKey(String),
     DuplicateTensorName(String),
+    /// The first dimension of the tensor is not divisible by the block size of its type.
+    IndivisibleShape(String),
 }
 
 impl fmt::Display for GGufError {
+    #[allow(deprecated)]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Reading(e) => write!(f, "reading error: {e:?}"),
+            Self::Io(e) => write!(f, "io error: {e}"),
             Self::MagicMismatch => f.write_str("magic mismatch"),
             Self::EndianNotSupport => f.write_str("endian not support"),
             Self::VersionNotSupport => f.write_str("version not support"),
             Self::AlignmentTypeMismatch(ty) => write!(f, "alignment type mismatch: {ty:?}"),
+            Self::InvalidAlignment(a) => write!(f, "invalid alignment: {a}"),
+            Self::InvalidMetaType(ty) => write!(f, "invalid meta value type: {ty}"),
+            Self::InvalidTensorType(ty) => write!(f, "invalid tensor type: {ty}"),
             Self::DuplicateMetaKey(key) => write!(f, "duplicate meta key: {key}"),
             Self::DuplicateTensorName(name) => write!(f, "duplicate tensor name: {name}"),
+            Self::IndivisibleShape(name) => write!(f, "indivisible shape of tensor: {name}"),
         }
     }
 }
@@ -44,6 +60,11 @@ impl GGufMetaMap for GGuf<'_> {
     fn get(&self, key: &str) -> Option<(GGufMetaDataValueType, &[u8])> {
         self.meta_kvs.get(key).map(|kv| (kv.ty(), kv.value_bytes()))
     }
+
+    #[inline]
+    fn value_reader<'b>(&self, val: &'b [u8]) -> GGufReader<'b> {
+        GGufReader::with_format(val, self.native_endian, self.header.version)
+    }
 }
 
 impl<'a> GGuf<'a> {
@@ -56,10 +77,7 @@ impl<'a> GGuf<'a> {
         if !header.is_magic_correct() {
             return Err(MagicMismatch);
         }
-        if !header.is_native_endian() {
-            return Err(EndianNotSupport);
-        }
-        if header.version != 3 {
+        if !(1..=3).contains(&header.version) {
             return Err(VersionNotSupport);
         }
 
@@ -87,7 +105,10 @@ impl<'a> GGuf<'a> {
             let tensor = reader.read_tensor_meta().map_err(Reading)?;
             let name = tensor.name();
             let info = tensor.to_info();
-            let end = info.offset() + info.nbytes();
+            let Some(nbytes) = info.ty().size().checked_elements_to_bytes(info.shape()) else {
+                return Err(IndivisibleShape(name.into()));
+            };
+            let end = info.offset() + nbytes;
             if end > data_len {
                 data_len = end;
             }
@@ -110,6 +131,7 @@ impl<'a> GGuf<'a> {
 
         Ok(Self {
             header,
+            native_endian: reader.is_native_endian(),
             alignment,
             meta_kvs,
             tensors,
@@ -117,3 +139,111 @@ impl<'a> GGuf<'a> {
         })
     }
 }
+
+#[test]
+fn test_big_endian() {
+    use crate::{GGmlType, GGufMetaMapExt};
+
+    // 构造一个非本机字节序的文件
+    let mut data = Vec::new();
+    macro_rules! push {
+        ($($val:expr),+) => { $( data.extend($val.swap_bytes().to_ne_bytes()); )+ };
+    }
+    macro_rules! push_str {
+        ($s:expr) => {{
+            push!($s.len() as u64);
+            data.extend($s.as_bytes());
+        }};
+    }
+
+    data.extend(b"GGUF");
+    push!(3u32, 1u64, 3u64);
+    push_str!(GENERAL_ALIGNMENT);
+    push!(GGufMetaDataValueType::U32 as u32, 64u32);
+    push_str!("general.architecture");
+    push!(GGufMetaDataValueType::String as u32);
+    push_str!("llama");
+    push_str!("tokenizer.ggml.token_type");
+    push!(GGufMetaDataValueType::Array as u32);
+    push!(GGufMetaDataValueType::I32 as u32, 2u64, 1i32, -1i32);
+    push_str!("x");
+    push!(2u32, 4u64, 2u64, GGmlType::F32 as u32, 0u64);
+    data.resize(data.len() + pad(data.len(), 64), 0);
+    for i in 0..8 {
+        push!((i as f32).to_bits());
+    }
+
+    let gguf = GGuf::new(&data).unwrap();
+    assert!(!gguf.native_endian);
+    assert_eq!(gguf.header.version, 3);
+    assert_eq!(gguf.header.tensor_count, 1);
+    assert_eq!(gguf.header.metadata_kv_count, 3);
+    assert_eq!(gguf.alignment, 64);
+    assert_eq!(gguf.general_architecture().unwrap(), "llama");
+    assert_eq!(
+        gguf.tokenizer_ggml_token_type()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        [1, -1]
+    );
+
+    let info = gguf.tensors["x"].to_info();
+    assert_eq!(info.ty(), GGmlType::F32);
+    assert_eq!(info.shape(), [4, 2]);
+    assert_eq!(info.offset(), 0);
+    assert_eq!(info.nbytes(), gguf.data.len());
+}
+
+#[test]
+fn test_version_1() {
+    use crate::{GGmlType, GGufMetaMapExt};
+
+    // 构造一个 v1 文件，长度、数量和维度都是 u32
+    let mut data = Vec::new();
+    macro_rules! push {
+        ($($val:expr),+) => { $( data.extend($val.to_ne_bytes()); )+ };
+    }
+    macro_rules! push_str {
+        ($s:expr) => {{
+            push!($s.len() as u32);
+            data.extend($s.as_bytes());
+        }};
+    }
+
+    data.extend(b"GGUF");
+    push!(1u32, 1u32, 2u32);
+    push_str!("general.architecture");
+    push!(GGufMetaDataValueType::String as u32);
+    push_str!("llama");
+    push_str!("tokenizer.ggml.tokens");
+    push!(GGufMetaDataValueType::Array as u32);
+    push!(GGufMetaDataValueType::String as u32, 2u32);
+    push_str!("a");
+    push_str!("bc");
+    push_str!("x");
+    push!(2u32, 4u32, 2u32, GGmlType::F32 as u32, 0u64);
+    data.resize(data.len() + pad(data.len(), DEFAULT_ALIGNMENT), 0);
+    for i in 0..8 {
+        push!(i as f32);
+    }
+
+    let gguf = GGuf::new(&data).unwrap();
+    assert!(gguf.native_endian);
+    assert_eq!(gguf.header.version, 1);
+    assert_eq!(gguf.header.tensor_count, 1);
+    assert_eq!(gguf.header.metadata_kv_count, 2);
+    assert_eq!(gguf.general_architecture().unwrap(), "llama");
+    assert_eq!(
+        gguf.tokenizer_ggml_tokens()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        ["a", "bc"]
+    );
+
+    let info = gguf.tensors["x"].to_info();
+    assert_eq!(info.ty(), GGmlType::F32);
+    assert_eq!(info.shape(), [4, 2]);
+    assert_eq!(info.nbytes(), gguf.data.len());
+}
@@ -2,6 +2,7 @@
 use std::str::{from_utf8, Utf8Error};
 
 #[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct GGufFileHeader {
     magic: [u8; 4],
@@ -13,11 +14,29 @@ pub struct GGufFileHeader {
 const MAGIC: [u8; 4] = *b"GGUF";
 
 impl GGufReader<'_> {
-    #[inline]
+    /// Reads the file header and detects the byte order and version of the file.
+    ///
+    /// The reader switches to the format of the file for subsequent reads,
+    /// and the header is returned in native byte order.
     pub fn read_header(&mut self) -> Result<GGufFileHeader, GGufReadError> {
-        let ptr = self.remaining().as_ptr().cast::<GGufFileHeader>();
-        self.skip::<GGufFileHeader>(1)?;
-        Ok(unsafe { ptr.read() })
+        self.set_format(true, 3);
+        let magic = self.read()?;
+        let version = self.read::<u32>()?;
+
+        let native_endian = is_native_version(version);
+        let version = if native_endian {
+            version
+        } else {
+            version.swap_bytes()
+        };
+        self.set_format(native_endian, version);
+
+        Ok(GGufFileHeader {
+            magic,
+            version,
+            tensor_count: self.read_size()?,
+            metadata_kv_count: self.read_size()?,
+        })
     }
 }
 
@@ -39,11 +58,17 @@ impl GGufFileHeader {
 
     #[inline]
     pub const fn is_native_endian(&self) -> bool {
-        // 先判断 native endian 再判断 file endian
-        if u32::from_ne_bytes(MAGIC) == u32::from_le_bytes(MAGIC) {
-            self.version == u32::from_le(self.version)
-        } else {
-            self.version == u32::from_be(self.version)
+        is_native_version(self.version)
+    }
+
+    /// Returns the header with all numeric fields byte-swapped.
+    #[inline]
+    pub const fn swap_bytes(self) -> Self {
+        Self {
+            magic: self.magic,
+            version: self.version.swap_bytes(),
+            tensor_count: self.tensor_count.swap_bytes(),
+            metadata_kv_count: self.metadata_kv_count.swap_bytes(),
         }
     }
 
@@ -52,3 +77,9 @@ impl GGufFileHeader {
         from_utf8(&self.magic)
     }
 }
+
+#[inline(always)]
+const fn is_native_version(version: u32) -> bool {
+    // 版本号是个很小的数，字节序翻转后低位必然为 0
+    version & 0xffff != 0
+}
@@ -3,26 +3,52 @@
 
 pub extern crate ggml_quants;
 
+mod arch_check;
+#[cfg(feature = "tokio")]
+mod async_stream;
+mod document;
 mod file;
 mod header;
 mod metadata;
+mod model;
 mod name;
+mod patch;
 mod read;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod stream;
 mod tensor;
+mod tensor_name;
+mod validate;
+mod view;
 mod write;
 
+pub use arch_check::GGufArchIssue;
+#[cfg(feature = "tokio")]
+pub use async_stream::GGufAsyncStream;
+pub use document::{GGufDocument, GGufMetaBuf, GGufTensorBuf, GGufTensorData};
 pub use file::{GGuf, GGufError};
 pub use header::GGufFileHeader;
 pub use metadata::{
-    GGmlTokenType, GGufFileType, GGufMetaDataValueType, GGufMetaError, GGufMetaKV, GGufMetaMap,
-    GGufMetaMapExt, GGufMetaValueArray, DEFAULT_ALIGNMENT, GENERAL_ALIGNMENT,
+    GGmlTokenType, GGufBertHParams, GGufClipHParams, GGufFileType, GGufGemmaHParams, GGufHParams,
+    GGufHParamsError, GGufLlamaHParams, GGufMambaHParams, GGufMetaDataValueType, GGufMetaError,
+    GGufMetaKV, GGufMetaMap, GGufMetaMapExt, GGufMetaScalar, GGufMetaValue, GGufMetaValueArray,
+    GGufMiniCpmHParams, GGufPhi3HParams, GGufQwen2HParams, GGufRopeHParams, GGufRopeScalingType,
+    GGufTransformerHParams, DEFAULT_ALIGNMENT, GENERAL_ALIGNMENT, GENERAL_PADDING, SPLIT_COUNT,
+    SPLIT_NO, SPLIT_TENSORS_COUNT,
 };
+pub use model::{GGufModel, GGufModelError, GGufModelTensor};
 pub use name::{GGufFileName, GGufShardParseError};
+pub use patch::GGufMetaPatch;
 pub use read::{GGufReadError, GGufReader};
+pub use stream::GGufStream;
 pub use tensor::{GGmlType, GGmlTypeSize, GGufTensorInfo, GGufTensorMeta};
+pub use tensor_name::{GGufTensorKind, GGufTensorName, GGufTensorNameError, GGufTensorSuffix};
+pub use validate::{GGufDiagnostic, GGufDiagnosticKind, GGufSeverity};
+pub use view::{GGufTensorView, GGufTensorViewError};
 pub use write::{
-    DataFuture, GGufFileSimulator, GGufFileWriter, GGufTensorSimulator, GGufTensorWriter,
-    GGufWriter,
+    DataFuture, GGufFileSimulator, GGufFileWriter, GGufSeekDataWriter, GGufSeekTensorWriter,
+    GGufSeekWriter, GGufTensorSimulator, GGufTensorWriter, GGufWriter,
 };
 
 #[inline(always)]
@@ -1,8 +1,17 @@
-﻿use super::{GGufFileType, GGufMetaDataValueType as Ty, GGufMetaValueArray, DEFAULT_ALIGNMENT};
-use crate::{GGufReadError, GGufReader};
+﻿use super::{
+    GGufFileType, GGufMetaDataValueType as Ty, GGufMetaScalar, GGufMetaValue, GGufMetaValueArray,
+    DEFAULT_ALIGNMENT,
+};
+use crate::{GGufArchIssue, GGufReadError, GGufReader};
 
 pub trait GGufMetaMap {
     fn get(&self, key: &str) -> Option<(Ty, &[u8])>;
+
+    /// Creates a reader over value bytes returned by [GGufMetaMap::get], in the format of this map.
+    #[inline]
+    fn value_reader<'a>(&self, val: &'a [u8]) -> GGufReader<'a> {
+        GGufReader::new(val)
+    }
 }
 
 #[derive(Debug)]
@@ -18,115 +27,148 @@ pub trait GGufMetaMapExt: GGufMetaMap {
     fn get_str(&self, key: &str) -> Result<&str, GGufMetaError> {
         let (ty, val) = self.get(key).ok_or(GGufMetaError::NotExist)?;
         match ty {
-            Ty::String => GGufReader::new(val).read_str().map_err(GGufMetaError::Read),
+            Ty::String => self
+                .value_reader(val)
+                .read_str()
+                .map_err(GGufMetaError::Read),
             _ => Err(GGufMetaError::TypeMismatch(ty)),
         }
     }
 
-    fn get_usize(&self, key: &str) -> Result<usize, GGufMetaError> {
+    /// Decodes the value of `key`, nested arrays included.
+    fn get_value(&self, key: &str) -> Result<GGufMetaValue<'_>, GGufMetaError> {
         let (ty, val) = self.get(key).ok_or(GGufMetaError::NotExist)?;
-
-        macro_rules! read {
-            ($ty:ty) => {
-                GGufReader::new(val)
-                    .read::<$ty>()
-                    .map_err(GGufMetaError::Read)?
-            };
-        }
-        macro_rules! convert {
-            ($val:expr) => {
-                $val.try_into().map_err(|_| GGufMetaError::OutOfRange)?
-            };
-        }
-
-        #[rustfmt::skip]
-        let ans = match ty {
-            Ty::U8   =>          read!(u8 ).into(),
-            Ty::U16  =>          read!(u16).into(),
-            Ty::U32  => convert!(read!(u32)      ),
-            Ty::U64  => convert!(read!(u64)      ),
-            Ty::I8   => convert!(read!(i8 )      ),
-            Ty::I16  => convert!(read!(i16)      ),
-            Ty::I32  => convert!(read!(i32)      ),
-            Ty::I64  => convert!(read!(i64)      ),
-            Ty::Bool => if read!(bool) { 1 } else { 0 },
-            _        => return Err(GGufMetaError::TypeMismatch(ty)),
-        };
-
-        Ok(ans)
+        self.value_reader(val)
+            .read_meta_value(ty)
+            .map_err(GGufMetaError::Read)
     }
 
-    fn get_f32(&self, key: &str) -> Result<f32, GGufMetaError> {
+    fn get_usize(&self, key: &str) -> Result<usize, GGufMetaError> {
         let (ty, val) = self.get(key).ok_or(GGufMetaError::NotExist)?;
-        if ty == Ty::F32 {
-            GGufReader::new(val).read().map_err(GGufMetaError::Read)
-        } else {
-            Err(GGufMetaError::TypeMismatch(ty))
-        }
+        read_usize(&mut self.value_reader(val), ty)
     }
 
-    fn get_u32(&self, key: &str) -> Result<u32, GGufMetaError> {
+    /// Reads a scalar value as `T`, widening it if lossless.
+    fn get_scalar<T: GGufMetaScalar>(&self, key: &str) -> Result<T, GGufMetaError> {
         let (ty, val) = self.get(key).ok_or(GGufMetaError::NotExist)?;
-        if ty == Ty::U32 {
-            GGufReader::new(val).read().map_err(GGufMetaError::Read)
+        if T::accepts(ty) {
+            T::read_from(&mut self.value_reader(val), ty).map_err(GGufMetaError::Read)
         } else {
             Err(GGufMetaError::TypeMismatch(ty))
         }
     }
 
-    fn get_bool(&self, key: &str) -> Result<bool, GGufMetaError> {
+    /// Reads an array whose elements can be read as `T`, widening them if lossless.
+    fn get_arr<T: GGufMetaScalar>(
+        &self,
+        key: &str,
+    ) -> Result<GGufMetaValueArray<'_, T>, GGufMetaError> {
         let (ty, val) = self.get(key).ok_or(GGufMetaError::NotExist)?;
-        if ty == Ty::Bool {
-            GGufReader::new(val)
-                .read_bool()
-                .map_err(GGufMetaError::Read)
-        } else {
-            Err(GGufMetaError::TypeMismatch(ty))
-        }
-    }
-
-    fn get_str_arr(&self, key: &str) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
-        let (ty, val) = self.get(key).ok_or(GGufMetaError::NotExist)?;
-        let mut reader = GGufReader::new(val);
+        let mut reader = self.value_reader(val);
         let (ty, len) = match ty {
             Ty::Array => reader.read_arr_header().map_err(GGufMetaError::Read)?,
             ty => return Err(GGufMetaError::TypeMismatch(ty)),
         };
-        if ty == Ty::String {
-            Ok(GGufMetaValueArray::new(reader, len))
+        if T::accepts(ty) {
+            Ok(GGufMetaValueArray::new_typed(reader, ty, len))
         } else {
             Err(GGufMetaError::ArrTypeMismatch(ty))
         }
     }
 
-    fn get_i32_arr(&self, key: &str) -> Result<GGufMetaValueArray<i32>, GGufMetaError> {
+    /// Reads a per-layer value, which is either a scalar shared by all layers or an array with one element per layer.
+    fn get_scalar_at<T: GGufMetaScalar>(
+        &self,
+        key: &str,
+        layer: usize,
+    ) -> Result<T, GGufMetaError> {
+        match self.get_arr::<T>(key) {
+            Ok(mut arr) => arr
+                .nth(layer)
+                .ok_or(GGufMetaError::OutOfRange)?
+                .map_err(GGufMetaError::Read),
+            Err(GGufMetaError::TypeMismatch(_)) => self.get_scalar(key),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads a per-layer value as usize, see [GGufMetaMapExt::get_scalar_at].
+    fn get_usize_at(&self, key: &str, layer: usize) -> Result<usize, GGufMetaError> {
         let (ty, val) = self.get(key).ok_or(GGufMetaError::NotExist)?;
-        let mut reader = GGufReader::new(val);
-        let (ty, len) = match ty {
-            Ty::Array => reader.read_arr_header().map_err(GGufMetaError::Read)?,
-            ty => return Err(GGufMetaError::TypeMismatch(ty)),
+        let mut reader = self.value_reader(val);
+        if ty != Ty::Array {
+            return read_usize(&mut reader, ty);
+        }
+
+        let (ty, len) = reader.read_arr_header().map_err(GGufMetaError::Read)?;
+        if layer >= len {
+            return Err(GGufMetaError::OutOfRange);
+        }
+        let mut read = || {
+            read_usize(&mut reader, ty).map_err(|e| match e {
+                GGufMetaError::TypeMismatch(ty) => GGufMetaError::ArrTypeMismatch(ty),
+                e => e,
+            })
         };
-        if ty == Ty::I32 {
-            Ok(GGufMetaValueArray::new(reader, len))
-        } else {
-            Err(GGufMetaError::ArrTypeMismatch(ty))
+        for _ in 0..layer {
+            read()?;
         }
+        read()
     }
 
-    fn get_f32_arr(&self, key: &str) -> Result<GGufMetaValueArray<f32>, GGufMetaError> {
+    #[inline]
+    fn get_f32(&self, key: &str) -> Result<f32, GGufMetaError> {
+        self.get_scalar(key)
+    }
+
+    #[inline]
+    fn get_u32(&self, key: &str) -> Result<u32, GGufMetaError> {
+        self.get_scalar(key)
+    }
+
+    #[inline]
+    fn get_bool(&self, key: &str) -> Result<bool, GGufMetaError> {
+        self.get_scalar(key)
+    }
+
+    fn get_str_arr(&self, key: &str) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         let (ty, val) = self.get(key).ok_or(GGufMetaError::NotExist)?;
-        let mut reader = GGufReader::new(val);
+        let mut reader = self.value_reader(val);
         let (ty, len) = match ty {
             Ty::Array => reader.read_arr_header().map_err(GGufMetaError::Read)?,
             ty => return Err(GGufMetaError::TypeMismatch(ty)),
         };
-        if ty == Ty::F32 {
+        if ty == Ty::String {
             Ok(GGufMetaValueArray::new(reader, len))
         } else {
             Err(GGufMetaError::ArrTypeMismatch(ty))
         }
     }
 
+    #[inline]
+    fn get_i32_arr(&self, key: &str) -> Result<GGufMetaValueArray<'_, i32>, GGufMetaError> {
+        self.get_arr(key)
+    }
+
+    #[inline]
+    fn get_f32_arr(&self, key: &str) -> Result<GGufMetaValueArray<'_, f32>, GGufMetaError> {
+        self.get_arr(key)
+    }
+
+    /// Checks whether the model would load as its `general.architecture` in llama.cpp,
+    /// `tensors` are the names and shapes of all tensors of the model.
+    ///
+    /// Meta keys are checked for presence and exact types, tensors for presence and shapes.
+    #[inline]
+    fn check_arch<N, S>(&self, tensors: impl IntoIterator<Item = (N, S)>) -> Vec<GGufArchIssue>
+    where
+        Self: Sized,
+        N: AsRef<str>,
+        S: AsRef<[u64]>,
+    {
+        crate::arch_check::check_arch(self, tensors)
+    }
+
     #[inline]
     fn general_architecture(&self) -> Result<&str, GGufMetaError> {
         self.get_str("general.architecture")
@@ -227,17 +269,17 @@ pub trait GGufMetaMapExt: GGufMetaMap {
     }
 
     #[inline]
-    fn general_tags(&self) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
+    fn general_tags(&self) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         self.get_str_arr("general.tags")
     }
 
     #[inline]
-    fn general_languages(&self) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
+    fn general_languages(&self) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         self.get_str_arr("general.languages")
     }
 
     #[inline]
-    fn general_datasets(&self) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
+    fn general_datasets(&self) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         self.get_str_arr("general.datasets")
     }
 
@@ -315,95 +357,120 @@ pub trait GGufMetaMapExt: GGufMetaMap {
 
     #[inline]
     fn llm_context_length(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_usize(&format!("{llm}.context_length"))
     }
 
     #[inline]
     fn llm_embedding_length(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_usize(&format!("{llm}.embedding_length"))
     }
 
     #[inline]
     fn llm_block_count(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_usize(&format!("{llm}.block_count"))
     }
 
+    /// Per-layer arrays yield the value of the first layer.
     #[inline]
     fn llm_feed_forward_length(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
-        self.get_usize(&format!("{llm}.feed_forward_length"))
+        let llm = self.general_architecture()?;
+        self.get_usize_at(&format!("{llm}.feed_forward_length"), 0)
+    }
+
+    #[inline]
+    fn llm_feed_forward_length_at(&self, layer: usize) -> Result<usize, GGufMetaError> {
+        let llm = self.general_architecture()?;
+        self.get_usize_at(&format!("{llm}.feed_forward_length"), layer)
     }
 
     #[inline]
     fn llm_use_parallel_residual(&self) -> Result<bool, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_bool(&format!("{llm}.use_parallel_residual"))
     }
 
     #[inline]
     fn llm_tensor_data_layout(&self) -> Result<&str, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_str(&format!("{llm}.tensor_data_layout"))
     }
 
     #[inline]
     fn llm_expert_count(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_usize(&format!("{llm}.expert_count"))
     }
 
     #[inline]
     fn llm_expert_used_count(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_usize(&format!("{llm}.expert_used_count"))
     }
 
+    /// Per-layer arrays yield the value of the first layer.
     #[inline]
     fn llm_attention_head_count(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
-        self.get_usize(&format!("{llm}.attention.head_count"))
+        let llm = self.general_architecture()?;
+        self.get_usize_at(&format!("{llm}.attention.head_count"), 0)
+    }
+
+    #[inline]
+    fn llm_attention_head_count_at(&self, layer: usize) -> Result<usize, GGufMetaError> {
+        let llm = self.general_architecture()?;
+        self.get_usize_at(&format!("{llm}.attention.head_count"), layer)
     }
 
+    /// Per-layer arrays yield the value of the first layer.
     #[inline]
     fn llm_attention_head_count_kv(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
-        match self.get_usize(&format!("{llm}.attention.head_count_kv")) {
+        let llm = self.general_architecture()?;
+        match self.get_usize_at(&format!("{llm}.attention.head_count_kv"), 0) {
             Ok(n) => Ok(n),
             Err(GGufMetaError::NotExist) => self.llm_attention_head_count(),
             Err(e) => Err(e),
         }
     }
 
+    #[inline]
+    fn llm_attention_head_count_kv_at(&self, layer: usize) -> Result<usize, GGufMetaError> {
+        let llm = self.general_architecture()?;
+        match self.get_usize_at(&format!("{llm}.attention.head_count_kv"), layer) {
+            Ok(n) => Ok(n),
+            Err(GGufMetaError::NotExist) => self.llm_attention_head_count_at(layer),
+            Err(e) => Err(e),
+        }
+    }
+
     #[inline]
     fn llm_attention_max_alibi_bias(&self) -> Result<f32, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_f32(&format!("{llm}.attention.max_alibi_bias"))
     }
 
     #[inline]
     fn llm_attention_clamp_kqv(&self) -> Result<f32, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_f32(&format!("{llm}.attention.clamp_kqv"))
     }
 
     #[inline]
     fn llm_attention_layer_norm_epsilon(&self) -> Result<f32, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_f32(&format!("{llm}.attention.layer_norm_epsilon"))
     }
 
     #[inline]
     fn llm_attention_layer_norm_rms_epsilon(&self) -> Result<f32, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_f32(&format!("{llm}.attention.layer_norm_rms_epsilon"))
     }
 
     #[inline]
     fn llm_attention_key_length(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         match self.get_usize(&format!("{llm}.attention.key_length")) {
             Ok(n) => Ok(n),
             Err(GGufMetaError::NotExist) => {
@@ -417,7 +484,7 @@ pub trait GGufMetaMapExt: GGufMetaMap {
 
     #[inline]
     fn llm_attention_value_length(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         match self.get_usize(&format!("{llm}.attention.value_length")) {
             Ok(n) => Ok(n),
             Err(GGufMetaError::NotExist) => {
@@ -431,67 +498,67 @@ pub trait GGufMetaMapExt: GGufMetaMap {
 
     #[inline]
     fn llm_rope_dimension_count(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_usize(&format!("{llm}.rope.dimension_count"))
     }
 
     #[inline]
     fn llm_rope_freq_base(&self) -> Result<f32, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_f32(&format!("{llm}.rope.freq_base"))
     }
 
     #[inline]
     fn llm_rope_scaling_type(&self) -> Result<&str, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_str(&format!("{llm}.rope.scaling.type"))
     }
 
     #[inline]
     fn llm_rope_scaling_factor(&self) -> Result<f32, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
-        self.get_f32(&format!("{llm}.rope.scaling.type"))
+        let llm = self.general_architecture()?;
+        self.get_f32(&format!("{llm}.rope.scaling.factor"))
     }
 
     #[inline]
     fn llm_rope_scaling_original_context_length(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_usize(&format!("{llm}.rope.scaling.original_context_length"))
     }
 
     #[inline]
     fn llm_rope_scaling_finetuned(&self) -> Result<bool, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_bool(&format!("{llm}.rope.scaling.finetuned"))
     }
 
     #[inline]
     fn llm_rope_scale_linear(&self) -> Result<f32, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_f32(&format!("{llm}.rope.scale_linear"))
     }
 
     #[inline]
     fn llm_ssm_conv_kernel(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_usize(&format!("{llm}.ssm.conv_kernel"))
     }
 
     #[inline]
     fn llm_ssm_inner_size(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_usize(&format!("{llm}.ssm.inner_size"))
     }
 
     #[inline]
     fn llm_ssm_state_size(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_usize(&format!("{llm}.ssm.state_size"))
     }
 
     #[inline]
     fn llm_ssm_time_step_rank(&self) -> Result<usize, GGufMetaError> {
-        let llm = self.general_architecture().unwrap();
+        let llm = self.general_architecture()?;
         self.get_usize(&format!("{llm}.ssm.time_step_rank"))
     }
 
@@ -501,27 +568,27 @@ pub trait GGufMetaMapExt: GGufMetaMap {
     }
 
     #[inline]
-    fn tokenizer_ggml_tokens(&self) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
+    fn tokenizer_ggml_tokens(&self) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         self.get_str_arr("tokenizer.ggml.tokens")
     }
 
     #[inline]
-    fn tokenizer_ggml_scores(&self) -> Result<GGufMetaValueArray<f32>, GGufMetaError> {
+    fn tokenizer_ggml_scores(&self) -> Result<GGufMetaValueArray<'_, f32>, GGufMetaError> {
         self.get_f32_arr("tokenizer.ggml.scores")
     }
 
     #[inline]
-    fn tokenizer_ggml_token_type(&self) -> Result<GGufMetaValueArray<i32>, GGufMetaError> {
+    fn tokenizer_ggml_token_type(&self) -> Result<GGufMetaValueArray<'_, i32>, GGufMetaError> {
         self.get_i32_arr("tokenizer.ggml.token_type")
     }
 
     #[inline]
-    fn tokenizer_ggml_merges(&self) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
+    fn tokenizer_ggml_merges(&self) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         self.get_str_arr("tokenizer.ggml.merges")
     }
 
     #[inline]
-    fn tokenizer_ggml_added_tokens(&self) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
+    fn tokenizer_ggml_added_tokens(&self) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         self.get_str_arr("tokenizer.ggml.added_tokens")
     }
 
@@ -562,3 +629,117 @@ pub trait GGufMetaMapExt: GGufMetaMap {
 }
 
 impl<T: GGufMetaMap> GGufMetaMapExt for T {}
+
+fn read_usize(reader: &mut GGufReader, ty: Ty) -> Result<usize, GGufMetaError> {
+    macro_rules! read {
+        ($ty:ty) => {
+            reader.read::<$ty>().map_err(GGufMetaError::Read)?
+        };
+    }
+    macro_rules! convert {
+        ($val:expr) => {
+            $val.try_into().map_err(|_| GGufMetaError::OutOfRange)?
+        };
+    }
+
+    #[rustfmt::skip]
+    let ans = match ty {
+        Ty::U8   =>          read!(u8 ).into(),
+        Ty::U16  =>          read!(u16).into(),
+        Ty::U32  => convert!(read!(u32)      ),
+        Ty::U64  => convert!(read!(u64)      ),
+        Ty::I8   => convert!(read!(i8 )      ),
+        Ty::I16  => convert!(read!(i16)      ),
+        Ty::I32  => convert!(read!(i32)      ),
+        Ty::I64  => convert!(read!(i64)      ),
+        Ty::Bool => if reader.read_bool().map_err(GGufMetaError::Read)? { 1 } else { 0 },
+        _        => return Err(GGufMetaError::TypeMismatch(ty)),
+    };
+
+    Ok(ans)
+}
+
+#[test]
+fn test_typed_getters() {
+    use crate::GGufDocument;
+    use GGufMetaValue as V;
+
+    let mut doc = GGufDocument::new();
+    let mut set = |k: &str, v: V| {
+        doc.insert_meta_kv(k.to_string(), v.to_meta_buf().unwrap());
+    };
+    set("general.architecture", V::String("openelm".into()));
+    set("openelm.block_count", V::U32(3));
+    set(
+        "openelm.attention.head_count",
+        V::Array(Ty::U16, vec![V::U16(12), V::U16(16), V::U16(20)]),
+    );
+    set("openelm.attention.head_count_kv", V::U8(4));
+    set("a", V::I8(-1));
+    set("b", V::U16(65535));
+    set("c", V::Array(Ty::Bool, vec![V::Bool(true), V::Bool(false)]));
+    set("d", V::Array(Ty::I64, vec![V::I64(-1)]));
+
+    assert_eq!(doc.get_scalar::<i32>("a").unwrap(), -1);
+    assert_eq!(doc.get_scalar::<f64>("a").unwrap(), -1.);
+    assert!(matches!(
+        doc.get_scalar::<u32>("a"),
+        Err(GGufMetaError::TypeMismatch(Ty::I8))
+    ));
+    assert_eq!(doc.get_scalar::<u64>("b").unwrap(), 65535);
+    assert_eq!(doc.get_f32("b").unwrap(), 65535.);
+    assert!(matches!(
+        doc.get_scalar::<i16>("b"),
+        Err(GGufMetaError::TypeMismatch(Ty::U16))
+    ));
+    assert_eq!(
+        doc.get_arr::<bool>("c")
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        [true, false]
+    );
+    assert!(matches!(
+        doc.get_arr::<f64>("d"),
+        Err(GGufMetaError::ArrTypeMismatch(Ty::I64))
+    ));
+    assert_eq!(
+        doc.get_arr::<i64>("openelm.attention.head_count")
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        [12, 16, 20]
+    );
+    // 不指定元素类型时按 `T` 原样读取
+    let (_, val) = doc.get("openelm.attention.head_count").unwrap();
+    let mut reader = crate::GGufReader::new(val);
+    assert_eq!(reader.read_arr_header().unwrap(), (Ty::U16, 3));
+    assert_eq!(
+        GGufMetaValueArray::<u16>::new(reader, 3)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        [12, 16, 20]
+    );
+
+    assert_eq!(
+        doc.get_scalar_at::<u32>("openelm.block_count", 7).unwrap(),
+        3
+    );
+    assert_eq!(
+        doc.get_scalar_at::<u32>("openelm.attention.head_count", 1)
+            .unwrap(),
+        16
+    );
+    assert!(matches!(
+        doc.get_usize_at("d", 0),
+        Err(GGufMetaError::OutOfRange)
+    ));
+    assert_eq!(doc.llm_attention_head_count().unwrap(), 12);
+    assert_eq!(doc.llm_attention_head_count_kv().unwrap(), 4);
+    assert_eq!(doc.llm_attention_head_count_at(2).unwrap(), 20);
+    assert!(matches!(
+        doc.llm_attention_head_count_at(3),
+        Err(GGufMetaError::OutOfRange)
+    ));
+    assert_eq!(doc.llm_attention_head_count_kv_at(2).unwrap(), 4);
+}
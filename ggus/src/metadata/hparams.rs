@@ -0,0 +1,520 @@
+use super::{GGufMetaError, GGufMetaMap, GGufMetaMapExt};
+use std::{error::Error, fmt};
+
+/// Hyperparameters of a model, typed by `general.architecture`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum GGufHParams {
+    Llama(GGufLlamaHParams),
+    Qwen2(GGufQwen2HParams),
+    Gemma(GGufGemmaHParams),
+    Phi3(GGufPhi3HParams),
+    MiniCpm(GGufMiniCpmHParams),
+    Mamba(GGufMambaHParams),
+    Bert(GGufBertHParams),
+    Clip(GGufClipHParams),
+}
+
+#[derive(Debug)]
+pub enum GGufHParamsError {
+    /// `general.architecture` is not one of the supported architectures.
+    UnsupportedArchitecture(String),
+    /// `general.architecture` is not the architecture of the requested hyperparameters.
+    ArchitectureMismatch {
+        expected: &'static str,
+        found: String,
+    },
+    /// A key is missing or has a bad value.
+    Meta { key: String, error: GGufMetaError },
+}
+
+impl fmt::Display for GGufHParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedArchitecture(arch) => write!(f, "unsupported architecture: {arch}"),
+            Self::ArchitectureMismatch { expected, found } => {
+                write!(f, "architecture is {found}, expected {expected}")
+            }
+            Self::Meta {
+                key,
+                error: GGufMetaError::NotExist,
+            } => write!(f, "missing {key}"),
+            Self::Meta { key, error } => write!(f, "bad {key}: {error:?}"),
+        }
+    }
+}
+
+impl Error for GGufHParamsError {}
+
+impl GGufHParams {
+    /// Reads the hyperparameters of the architecture named by `general.architecture`.
+    pub fn from_meta<M: GGufMetaMap>(meta: &M) -> Result<Self, GGufHParamsError> {
+        let arch = Keys::new(meta, "general").str("architecture")?;
+        match arch {
+            GGufLlamaHParams::ARCHITECTURE => GGufLlamaHParams::from_meta(meta).map(Self::Llama),
+            GGufQwen2HParams::ARCHITECTURE => GGufQwen2HParams::from_meta(meta).map(Self::Qwen2),
+            GGufGemmaHParams::ARCHITECTURE => GGufGemmaHParams::from_meta(meta).map(Self::Gemma),
+            GGufPhi3HParams::ARCHITECTURE => GGufPhi3HParams::from_meta(meta).map(Self::Phi3),
+            GGufMiniCpmHParams::ARCHITECTURE => {
+                GGufMiniCpmHParams::from_meta(meta).map(Self::MiniCpm)
+            }
+            GGufMambaHParams::ARCHITECTURE => GGufMambaHParams::from_meta(meta).map(Self::Mamba),
+            GGufBertHParams::ARCHITECTURE => GGufBertHParams::from_meta(meta).map(Self::Bert),
+            GGufClipHParams::ARCHITECTURE => GGufClipHParams::from_meta(meta).map(Self::Clip),
+            arch => Err(GGufHParamsError::UnsupportedArchitecture(arch.into())),
+        }
+    }
+}
+
+/// Rotary position embedding, shared by the decoder-only transformers.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GGufRopeHParams {
+    /// Defaults to the key length.
+    pub dimension_count: usize,
+    /// Defaults to 10000.
+    pub freq_base: f32,
+    /// Defaults to [GGufRopeScalingType::Linear] if the factor is not 1, or [GGufRopeScalingType::None] otherwise.
+    pub scaling_type: GGufRopeScalingType,
+    /// Read from `rope.scaling.factor`, or the legacy `rope.scale_linear`, defaults to 1.
+    pub scaling_factor: f32,
+    /// Defaults to the context length.
+    pub scaling_original_context_length: usize,
+    /// Defaults to false.
+    pub scaling_finetuned: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GGufRopeScalingType {
+    None,
+    Linear,
+    Yarn,
+    LongRope,
+}
+
+impl GGufRopeScalingType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Linear => "linear",
+            Self::Yarn => "yarn",
+            Self::LongRope => "longrope",
+        }
+    }
+}
+
+/// Hyperparameters common to the decoder-only transformers.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GGufTransformerHParams {
+    pub context_length: usize,
+    pub embedding_length: usize,
+    pub block_count: usize,
+    /// Of the first layer if given per layer, so are the numbers of heads.
+    pub feed_forward_length: usize,
+    pub head_count: usize,
+    /// Defaults to the number of heads.
+    pub head_count_kv: usize,
+    /// Defaults to the embedding length divided by the number of heads.
+    pub key_length: usize,
+    /// Defaults to the embedding length divided by the number of heads.
+    pub value_length: usize,
+    pub rms_epsilon: f32,
+    pub rope: GGufRopeHParams,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GGufLlamaHParams {
+    pub transformer: GGufTransformerHParams,
+    /// Defaults to 0 for dense models.
+    pub expert_count: usize,
+    /// Defaults to 0 for dense models.
+    pub expert_used_count: usize,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GGufQwen2HParams {
+    pub transformer: GGufTransformerHParams,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GGufGemmaHParams {
+    pub transformer: GGufTransformerHParams,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GGufPhi3HParams {
+    pub transformer: GGufTransformerHParams,
+    pub sliding_window: Option<usize>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GGufMiniCpmHParams {
+    pub transformer: GGufTransformerHParams,
+    pub embedding_scale: f32,
+    pub residual_scale: f32,
+    pub logit_scale: f32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GGufMambaHParams {
+    pub context_length: usize,
+    pub embedding_length: usize,
+    pub block_count: usize,
+    pub conv_kernel: usize,
+    pub inner_size: usize,
+    pub state_size: usize,
+    pub time_step_rank: usize,
+    pub rms_epsilon: f32,
+    /// Defaults to false.
+    pub dt_b_c_rms: bool,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GGufBertHParams {
+    pub context_length: usize,
+    pub embedding_length: usize,
+    pub block_count: usize,
+    pub feed_forward_length: usize,
+    pub head_count: usize,
+    pub layer_norm_epsilon: f32,
+    /// Defaults to false.
+    pub causal: bool,
+    pub pooling_type: Option<u32>,
+}
+
+/// Hyperparameters of the vision encoder of a clip model, read from the `clip.vision.*` keys.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GGufClipHParams {
+    pub image_size: usize,
+    pub patch_size: usize,
+    pub embedding_length: usize,
+    pub feed_forward_length: usize,
+    pub block_count: usize,
+    pub head_count: usize,
+    pub layer_norm_epsilon: f32,
+    pub projection_dim: Option<usize>,
+    pub projector_type: Option<String>,
+}
+
+impl GGufTransformerHParams {
+    fn from_keys<M: GGufMetaMap>(keys: &Keys<M>) -> Result<Self, GGufHParamsError> {
+        let context_length = keys.usize("context_length")?;
+        let embedding_length = keys.usize("embedding_length")?;
+        let head_count = keys.layer_usize("attention.head_count")?;
+        let head_dim = embedding_length
+            .checked_div(head_count)
+            .ok_or_else(|| keys.error("attention.head_count", GGufMetaError::OutOfRange))?;
+        let key_length = keys.opt_usize("attention.key_length")?.unwrap_or(head_dim);
+
+        let scaling_factor = match keys.opt_f32("rope.scaling.factor")? {
+            Some(factor) => Some(factor),
+            None => keys.opt_f32("rope.scale_linear")?,
+        }
+        .filter(|&factor| factor != 0.)
+        .unwrap_or(1.);
+        let scaling_type = match keys.opt_str("rope.scaling.type")? {
+            Some("none") => GGufRopeScalingType::None,
+            Some("linear") => GGufRopeScalingType::Linear,
+            Some("yarn") => GGufRopeScalingType::Yarn,
+            Some("longrope") => GGufRopeScalingType::LongRope,
+            Some(_) => return Err(keys.error("rope.scaling.type", GGufMetaError::OutOfRange)),
+            None if scaling_factor != 1. => GGufRopeScalingType::Linear,
+            None => GGufRopeScalingType::None,
+        };
+
+        Ok(Self {
+            context_length,
+            embedding_length,
+            block_count: keys.usize("block_count")?,
+            feed_forward_length: keys.layer_usize("feed_forward_length")?,
+            head_count,
+            head_count_kv: keys
+                .opt("attention.head_count_kv", |m, k| m.get_usize_at(k, 0))?
+                .unwrap_or(head_count),
+            key_length,
+            value_length: keys
+                .opt_usize("attention.value_length")?
+                .unwrap_or(head_dim),
+            rms_epsilon: keys.f32("attention.layer_norm_rms_epsilon")?,
+            rope: GGufRopeHParams {
+                dimension_count: keys
+                    .opt_usize("rope.dimension_count")?
+                    .unwrap_or(key_length),
+                freq_base: keys.opt_f32("rope.freq_base")?.unwrap_or(10000.),
+                scaling_type,
+                scaling_factor,
+                scaling_original_context_length: keys
+                    .opt_usize("rope.scaling.original_context_length")?
+                    .unwrap_or(context_length),
+                scaling_finetuned: keys.opt_bool("rope.scaling.finetuned")?.unwrap_or(false),
+            },
+        })
+    }
+}
+
+impl GGufLlamaHParams {
+    pub const ARCHITECTURE: &'static str = "llama";
+
+    pub fn from_meta<M: GGufMetaMap>(meta: &M) -> Result<Self, GGufHParamsError> {
+        let keys = Keys::arch(meta, Self::ARCHITECTURE)?;
+        Ok(Self {
+            transformer: GGufTransformerHParams::from_keys(&keys)?,
+            expert_count: keys.opt_usize("expert_count")?.unwrap_or(0),
+            expert_used_count: keys.opt_usize("expert_used_count")?.unwrap_or(0),
+        })
+    }
+}
+
+impl GGufQwen2HParams {
+    pub const ARCHITECTURE: &'static str = "qwen2";
+
+    pub fn from_meta<M: GGufMetaMap>(meta: &M) -> Result<Self, GGufHParamsError> {
+        let keys = Keys::arch(meta, Self::ARCHITECTURE)?;
+        Ok(Self {
+            transformer: GGufTransformerHParams::from_keys(&keys)?,
+        })
+    }
+}
+
+impl GGufGemmaHParams {
+    pub const ARCHITECTURE: &'static str = "gemma";
+
+    pub fn from_meta<M: GGufMetaMap>(meta: &M) -> Result<Self, GGufHParamsError> {
+        let keys = Keys::arch(meta, Self::ARCHITECTURE)?;
+        Ok(Self {
+            transformer: GGufTransformerHParams::from_keys(&keys)?,
+        })
+    }
+}
+
+impl GGufPhi3HParams {
+    pub const ARCHITECTURE: &'static str = "phi3";
+
+    pub fn from_meta<M: GGufMetaMap>(meta: &M) -> Result<Self, GGufHParamsError> {
+        let keys = Keys::arch(meta, Self::ARCHITECTURE)?;
+        Ok(Self {
+            transformer: GGufTransformerHParams::from_keys(&keys)?,
+            sliding_window: keys.opt_usize("attention.sliding_window")?,
+        })
+    }
+}
+
+impl GGufMiniCpmHParams {
+    pub const ARCHITECTURE: &'static str = "minicpm";
+
+    pub fn from_meta<M: GGufMetaMap>(meta: &M) -> Result<Self, GGufHParamsError> {
+        let keys = Keys::arch(meta, Self::ARCHITECTURE)?;
+        Ok(Self {
+            transformer: GGufTransformerHParams::from_keys(&keys)?,
+            embedding_scale: keys.f32("embedding_scale")?,
+            residual_scale: keys.f32("residual_scale")?,
+            logit_scale: keys.f32("logit_scale")?,
+        })
+    }
+}
+
+impl GGufMambaHParams {
+    pub const ARCHITECTURE: &'static str = "mamba";
+
+    pub fn from_meta<M: GGufMetaMap>(meta: &M) -> Result<Self, GGufHParamsError> {
+        let keys = Keys::arch(meta, Self::ARCHITECTURE)?;
+        Ok(Self {
+            context_length: keys.usize("context_length")?,
+            embedding_length: keys.usize("embedding_length")?,
+            block_count: keys.usize("block_count")?,
+            conv_kernel: keys.usize("ssm.conv_kernel")?,
+            inner_size: keys.usize("ssm.inner_size")?,
+            state_size: keys.usize("ssm.state_size")?,
+            time_step_rank: keys.usize("ssm.time_step_rank")?,
+            rms_epsilon: keys.f32("attention.layer_norm_rms_epsilon")?,
+            dt_b_c_rms: keys.opt_bool("ssm.dt_b_c_rms")?.unwrap_or(false),
+        })
+    }
+}
+
+impl GGufBertHParams {
+    pub const ARCHITECTURE: &'static str = "bert";
+
+    pub fn from_meta<M: GGufMetaMap>(meta: &M) -> Result<Self, GGufHParamsError> {
+        let keys = Keys::arch(meta, Self::ARCHITECTURE)?;
+        Ok(Self {
+            context_length: keys.usize("context_length")?,
+            embedding_length: keys.usize("embedding_length")?,
+            block_count: keys.usize("block_count")?,
+            feed_forward_length: keys.usize("feed_forward_length")?,
+            head_count: keys.usize("attention.head_count")?,
+            layer_norm_epsilon: keys.f32("attention.layer_norm_epsilon")?,
+            causal: keys.opt_bool("attention.causal")?.unwrap_or(false),
+            pooling_type: keys.opt_u32("pooling_type")?,
+        })
+    }
+}
+
+impl GGufClipHParams {
+    pub const ARCHITECTURE: &'static str = "clip";
+
+    pub fn from_meta<M: GGufMetaMap>(meta: &M) -> Result<Self, GGufHParamsError> {
+        Keys::arch(meta, Self::ARCHITECTURE)?;
+        let keys = Keys::new(meta, "clip.vision");
+        Ok(Self {
+            image_size: keys.usize("image_size")?,
+            patch_size: keys.usize("patch_size")?,
+            embedding_length: keys.usize("embedding_length")?,
+            feed_forward_length: keys.usize("feed_forward_length")?,
+            block_count: keys.usize("block_count")?,
+            head_count: keys.usize("attention.head_count")?,
+            layer_norm_epsilon: keys.f32("attention.layer_norm_epsilon")?,
+            projection_dim: keys.opt_usize("projection_dim")?,
+            projector_type: Keys::new(meta, "clip")
+                .opt_str("projector_type")?
+                .map(Into::into),
+        })
+    }
+}
+
+/// 读取同一前缀下的键，错误中带上完整的键名
+struct Keys<'a, M> {
+    meta: &'a M,
+    prefix: &'a str,
+}
+
+impl<'a, M: GGufMetaMap> Keys<'a, M> {
+    #[inline]
+    fn new(meta: &'a M, prefix: &'a str) -> Self {
+        Self { meta, prefix }
+    }
+
+    fn arch(meta: &'a M, arch: &'static str) -> Result<Self, GGufHParamsError> {
+        match Keys::new(meta, "general").str("architecture")? {
+            found if found == arch => Ok(Self::new(meta, arch)),
+            found => Err(GGufHParamsError::ArchitectureMismatch {
+                expected: arch,
+                found: found.into(),
+            }),
+        }
+    }
+
+    fn error(&self, name: &str, error: GGufMetaError) -> GGufHParamsError {
+        GGufHParamsError::Meta {
+            key: format!("{}.{name}", self.prefix),
+            error,
+        }
+    }
+
+    fn opt<T>(
+        &self,
+        name: &str,
+        get: impl FnOnce(&'a M, &str) -> Result<T, GGufMetaError>,
+    ) -> Result<Option<T>, GGufHParamsError> {
+        match get(self.meta, &format!("{}.{name}", self.prefix)) {
+            Ok(val) => Ok(Some(val)),
+            Err(GGufMetaError::NotExist) => Ok(None),
+            Err(e) => Err(self.error(name, e)),
+        }
+    }
+
+    fn req<T>(
+        &self,
+        name: &str,
+        get: impl FnOnce(&'a M, &str) -> Result<T, GGufMetaError>,
+    ) -> Result<T, GGufHParamsError> {
+        self.opt(name, get)?
+            .ok_or_else(|| self.error(name, GGufMetaError::NotExist))
+    }
+
+    #[inline]
+    fn usize(&self, name: &str) -> Result<usize, GGufHParamsError> {
+        self.req(name, |m, k| m.get_usize(k))
+    }
+
+    #[inline]
+    fn f32(&self, name: &str) -> Result<f32, GGufHParamsError> {
+        self.req(name, |m, k| m.get_f32(k))
+    }
+
+    #[inline]
+    fn str(&self, name: &str) -> Result<&'a str, GGufHParamsError> {
+        self.req(name, |m, k| m.get_str(k))
+    }
+
+    /// 逐层给出的值取第一层
+    #[inline]
+    fn layer_usize(&self, name: &str) -> Result<usize, GGufHParamsError> {
+        self.req(name, |m, k| m.get_usize_at(k, 0))
+    }
+
+    #[inline]
+    fn opt_usize(&self, name: &str) -> Result<Option<usize>, GGufHParamsError> {
+        self.opt(name, |m, k| m.get_usize(k))
+    }
+
+    #[inline]
+    fn opt_u32(&self, name: &str) -> Result<Option<u32>, GGufHParamsError> {
+        self.opt(name, |m, k| m.get_u32(k))
+    }
+
+    #[inline]
+    fn opt_f32(&self, name: &str) -> Result<Option<f32>, GGufHParamsError> {
+        self.opt(name, |m, k| m.get_f32(k))
+    }
+
+    #[inline]
+    fn opt_bool(&self, name: &str) -> Result<Option<bool>, GGufHParamsError> {
+        self.opt(name, |m, k| m.get_bool(k))
+    }
+
+    #[inline]
+    fn opt_str(&self, name: &str) -> Result<Option<&'a str>, GGufHParamsError> {
+        self.opt(name, |m, k| m.get_str(k))
+    }
+}
+
+#[test]
+fn test_hparams() {
+    use crate::{GGufDocument, GGufMetaValue as V};
+
+    let mut doc = GGufDocument::new();
+    let mut set = |k: &str, v: V| {
+        doc.insert_meta_kv(k.to_string(), v.to_meta_buf().unwrap());
+    };
+    set("general.architecture", V::String("llama".into()));
+    set("llama.context_length", V::U32(2048));
+    set("llama.embedding_length", V::U32(256));
+    set("llama.block_count", V::U32(4));
+    set("llama.feed_forward_length", V::U32(768));
+    set("llama.attention.head_count", V::U32(8));
+    set("llama.attention.layer_norm_rms_epsilon", V::F32(1e-5));
+    set("llama.rope.scale_linear", V::F32(2.));
+
+    let GGufHParams::Llama(llama) = GGufHParams::from_meta(&doc).unwrap() else {
+        panic!()
+    };
+    let t = &llama.transformer;
+    assert_eq!((t.head_count_kv, t.key_length, t.value_length), (8, 32, 32));
+    assert_eq!(t.rope.dimension_count, 32);
+    assert_eq!(t.rope.freq_base, 10000.);
+    assert_eq!(t.rope.scaling_type, GGufRopeScalingType::Linear);
+    assert_eq!(t.rope.scaling_factor, 2.);
+    assert_eq!(t.rope.scaling_original_context_length, 2048);
+    assert_eq!((llama.expert_count, llama.expert_used_count), (0, 0));
+
+    doc.insert_meta_kv(
+        "llama.rope.scaling.factor",
+        V::F32(4.).to_meta_buf().unwrap(),
+    );
+    doc.insert_meta_kv(
+        "llama.rope.scaling.type",
+        V::String("yarn".into()).to_meta_buf().unwrap(),
+    );
+    let rope = GGufLlamaHParams::from_meta(&doc).unwrap().transformer.rope;
+    assert_eq!(rope.scaling_type, GGufRopeScalingType::Yarn);
+    assert_eq!(rope.scaling_factor, 4.);
+
+    assert!(matches!(
+        GGufQwen2HParams::from_meta(&doc),
+        Err(GGufHParamsError::ArchitectureMismatch { expected: "qwen2", found }) if found == "llama"
+    ));
+    doc.meta_kvs.shift_remove("llama.block_count");
+    assert_eq!(
+        GGufHParams::from_meta(&doc).unwrap_err().to_string(),
+        "missing llama.block_count"
+    );
+}
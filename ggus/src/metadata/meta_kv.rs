@@ -1,10 +1,10 @@
-﻿use super::GGufMetaDataValueType as Ty;
+﻿use super::{GGufMetaDataValueType as Ty, GGufMetaValue};
 use crate::{GGufReadError, GGufReader};
-use std::marker::PhantomData;
+use std::{marker::PhantomData, mem::MaybeUninit};
 
 #[derive(Clone)]
 #[repr(transparent)]
-pub struct GGufMetaKV<'a>(&'a [u8]);
+pub struct GGufMetaKV<'a>(GGufReader<'a>);
 
 impl<'a> GGufReader<'a> {
     pub fn read_meta_kv(&mut self) -> Result<GGufMetaKV<'a>, GGufReadError> {
@@ -12,13 +12,13 @@ impl<'a> GGufReader<'a> {
 
         let _k = self.read_str()?;
         let ty = self.read()?;
-        self.read_meta_value(ty, 1)?;
+        self.skip_meta_value(ty, 1)?;
 
         let data = &data[..data.len() - self.remaining().len()];
-        Ok(unsafe { GGufMetaKV::new_unchecked(data) })
+        Ok(unsafe { GGufMetaKV::from_reader_unchecked(self.with_data(data)) })
     }
 
-    fn read_meta_value(&mut self, ty: Ty, len: usize) -> Result<&mut Self, GGufReadError> {
+    fn skip_meta_value(&mut self, ty: Ty, len: usize) -> Result<&mut Self, GGufReadError> {
         match ty {
             Ty::U8 => self.skip::<u8>(len),
             Ty::I8 => self.skip::<i8>(len),
@@ -43,8 +43,11 @@ impl<'a> GGufReader<'a> {
                 Ok(self)
             }
             Ty::Array => {
-                let (ty, len) = self.read_arr_header()?;
-                self.read_meta_value(ty, len)
+                for _ in 0..len {
+                    let (ty, len) = self.read_arr_header()?;
+                    self.skip_meta_value(ty, len)?;
+                }
+                Ok(self)
             }
         }
     }
@@ -58,7 +61,18 @@ impl<'a> GGufMetaKV<'a> {
     /// The caller must ensure that the input data is valid for the [GGufMetaKV] type.
     #[inline]
     pub const unsafe fn new_unchecked(data: &'a [u8]) -> Self {
-        Self(data)
+        Self(GGufReader::new(data))
+    }
+
+    /// Creates a new [GGufMetaKV] instance from the remaining data of a reader, in the format of the reader,
+    /// without performing any validation on the input data.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the remaining data of the reader is valid for the [GGufMetaKV] type.
+    #[inline]
+    pub const unsafe fn from_reader_unchecked(reader: GGufReader<'a>) -> Self {
+        Self(reader)
     }
 
     #[inline]
@@ -66,6 +80,11 @@ impl<'a> GGufMetaKV<'a> {
         GGufReader::new(data).read_meta_kv()
     }
 
+    #[inline]
+    pub const fn is_native_endian(&self) -> bool {
+        self.0.is_native_endian()
+    }
+
     #[inline]
     pub fn key(&self) -> &'a str {
         let mut reader = self.reader();
@@ -92,6 +111,12 @@ impl<'a> GGufMetaKV<'a> {
         reader
     }
 
+    /// Decodes the value, nested arrays included.
+    #[inline]
+    pub fn value(&self) -> Result<GGufMetaValue<'a>, GGufReadError> {
+        self.value_reader().read_meta_value(self.ty())
+    }
+
     pub fn read_integer(&self) -> isize {
         let mut reader = self.reader();
         let ty = reader.skip_str().unwrap().read::<Ty>().unwrap();
@@ -126,25 +151,39 @@ impl<'a> GGufMetaKV<'a> {
 
     #[inline]
     fn reader(&self) -> GGufReader<'a> {
-        GGufReader::new(self.0)
+        self.0.clone()
     }
 }
 
 pub struct GGufMetaValueArray<'a, T: ?Sized> {
     reader: GGufReader<'a>,
+    ty: Option<Ty>,
     len: usize,
+    /// 按 `ty` 读取一个元素并转换为 `T` 写入指针，为 `None` 时按 `T` 原样读取
+    read: Option<ReadElement<'a>>,
     _phantom: PhantomData<T>,
 }
 
+type ReadElement<'a> = fn(&mut GGufReader<'a>, Ty, *mut u8) -> Result<(), GGufReadError>;
+
 impl<'a, T: ?Sized> GGufMetaValueArray<'a, T> {
+    /// Creates an iterator over `len` elements read from `reader` as `T` as is.
     pub fn new(reader: GGufReader<'a>, len: usize) -> Self {
         Self {
             reader,
+            ty: None,
             len,
+            read: None,
             _phantom: PhantomData,
         }
     }
 
+    /// Type of the elements stored in the file, if known.
+    #[inline]
+    pub const fn ty(&self) -> Option<Ty> {
+        self.ty
+    }
+
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.len == 0
@@ -156,6 +195,35 @@ impl<'a, T: ?Sized> GGufMetaValueArray<'a, T> {
     }
 }
 
+impl<'a, T: GGufMetaScalar> GGufMetaValueArray<'a, T> {
+    /// Creates an iterator over `len` elements of type `ty` from `reader`, widening them to `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ty` is not accepted by [GGufMetaScalar::accepts].
+    pub fn new_typed(reader: GGufReader<'a>, ty: Ty, len: usize) -> Self {
+        fn read<'a, T: GGufMetaScalar>(
+            reader: &mut GGufReader<'a>,
+            ty: Ty,
+            dst: *mut u8,
+        ) -> Result<(), GGufReadError> {
+            let val = T::read_from(reader, ty)?;
+            // 只有 `new_typed` 会设置此函数，`dst` 总是指向 `T`
+            unsafe { dst.cast::<T>().write(val) };
+            Ok(())
+        }
+
+        assert!(T::accepts(ty), "{ty:?} cannot be read as the element type");
+        Self {
+            reader,
+            ty: Some(ty),
+            len,
+            read: Some(read::<T>),
+            _phantom: PhantomData,
+        }
+    }
+}
+
 impl<'a> Iterator for GGufMetaValueArray<'a, str> {
     type Item = Result<&'a str, GGufReadError>;
     fn next(&mut self) -> Option<Self::Item> {
@@ -173,9 +241,70 @@ impl<T: Copy> Iterator for GGufMetaValueArray<'_, T> {
     fn next(&mut self) -> Option<Self::Item> {
         if self.len != 0 {
             self.len -= 1;
-            Some(self.reader.read())
+            Some(match (self.read, self.ty) {
+                (Some(read), Some(ty)) => {
+                    let mut val = MaybeUninit::<T>::uninit();
+                    read(&mut self.reader, ty, val.as_mut_ptr().cast())
+                        .map(|()| unsafe { val.assume_init() })
+                }
+                _ => self.reader.read(),
+            })
         } else {
             None
         }
     }
 }
+
+/// Scalar types which metadata values can be read as.
+///
+/// A value of another type is accepted if it can be converted losslessly,
+/// e.g. a `u16` value can be read as `u32`, `i32` or `f32`, but not as `i16`.
+pub trait GGufMetaScalar: Copy {
+    /// Whether values of type `ty` can be read as this type.
+    fn accepts(ty: Ty) -> bool;
+
+    /// Reads a value of type `ty`, which must be accepted by [GGufMetaScalar::accepts].
+    fn read_from(reader: &mut GGufReader, ty: Ty) -> Result<Self, GGufReadError>;
+}
+
+macro_rules! scalar {
+    ($ty:ty: $($src:ident $src_ty:ty),+) => {
+        impl GGufMetaScalar for $ty {
+            #[inline]
+            fn accepts(ty: Ty) -> bool {
+                matches!(ty, $(Ty::$src)|+)
+            }
+
+            fn read_from(reader: &mut GGufReader, ty: Ty) -> Result<Self, GGufReadError> {
+                match ty {
+                    $(Ty::$src => reader.read::<$src_ty>().map(Self::from),)+
+                    _ => panic!("{ty:?} cannot be read as {}", stringify!($ty)),
+                }
+            }
+        }
+    };
+}
+
+scalar!(u8 : U8 u8);
+scalar!(i8 : I8 i8);
+scalar!(u16: U8 u8, U16 u16);
+scalar!(i16: U8 u8, I8 i8, I16 i16);
+scalar!(u32: U8 u8, U16 u16, U32 u32);
+scalar!(i32: U8 u8, I8 i8, U16 u16, I16 i16, I32 i32);
+scalar!(u64: U8 u8, U16 u16, U32 u32, U64 u64);
+scalar!(i64: U8 u8, I8 i8, U16 u16, I16 i16, U32 u32, I32 i32, I64 i64);
+scalar!(f32: U8 u8, I8 i8, U16 u16, I16 i16, F32 f32);
+scalar!(f64: U8 u8, I8 i8, U16 u16, I16 i16, U32 u32, I32 i32, F32 f32, F64 f64);
+
+impl GGufMetaScalar for bool {
+    #[inline]
+    fn accepts(ty: Ty) -> bool {
+        ty == Ty::Bool
+    }
+
+    #[inline]
+    fn read_from(reader: &mut GGufReader, ty: Ty) -> Result<Self, GGufReadError> {
+        assert_eq!(ty, Ty::Bool);
+        reader.read_bool()
+    }
+}
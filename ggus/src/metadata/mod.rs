@@ -1,15 +1,37 @@
 //! See <https://github.com/ggerganov/ggml/blob/master/docs/gguf.md#standardized-key-value-pairs>.
 
 mod collection;
+mod hparams;
 mod meta_kv;
+mod value;
 
 pub use collection::{GGufMetaError, GGufMetaMap, GGufMetaMapExt};
-pub use meta_kv::{GGufMetaKV, GGufMetaValueArray};
+pub use hparams::{
+    GGufBertHParams, GGufClipHParams, GGufGemmaHParams, GGufHParams, GGufHParamsError,
+    GGufLlamaHParams, GGufMambaHParams, GGufMiniCpmHParams, GGufPhi3HParams, GGufQwen2HParams,
+    GGufRopeHParams, GGufRopeScalingType, GGufTransformerHParams,
+};
+pub use meta_kv::{GGufMetaKV, GGufMetaScalar, GGufMetaValueArray};
+pub use value::GGufMetaValue;
 
 pub const DEFAULT_ALIGNMENT: usize = 32;
 pub const GENERAL_ALIGNMENT: &str = "general.alignment";
+/// A `u8` array that fills the space left by in-place metadata patching, so that tensor data stays in place.
+pub const GENERAL_PADDING: &str = "general.padding";
+/// Zero-based index of the shard in a sharded model.
+pub const SPLIT_NO: &str = "split.no";
+/// Number of shards of a sharded model.
+pub const SPLIT_COUNT: &str = "split.count";
+/// Number of tensors in all shards of a sharded model.
+pub const SPLIT_TENSORS_COUNT: &str = "split.tensors.count";
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+/// 描述文件布局而非模型的元信息键，由写入器生成，不随模型内容读写。
+pub(crate) fn is_layout_key(key: &str) -> bool {
+    key == GENERAL_ALIGNMENT || key == GENERAL_PADDING || key.starts_with("split.")
+}
+
+#[derive(num_enum::TryFromPrimitive, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum GGufMetaDataValueType {
     /// The value is a 8-bit unsigned integer.
@@ -110,6 +132,7 @@ pub enum GGufFileType {
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum GGmlTokenType {
     Normal = 1,
@@ -0,0 +1,215 @@
+use super::GGufMetaDataValueType as Ty;
+use crate::{GGufMetaBuf, GGufReadError, GGufReader, GGufWriter};
+use std::{
+    borrow::Cow,
+    fmt,
+    io::{self, Write},
+};
+
+/// A decoded metadata value.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GGufMetaValue<'a> {
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    F32(#[cfg_attr(feature = "serde", serde(with = "crate::serde_impl::f32_bits"))] f32),
+    Bool(bool),
+    String(Cow<'a, str>),
+    /// An array with the type of its elements, which may be arrays themselves.
+    Array(Ty, Vec<GGufMetaValue<'a>>),
+    U64(u64),
+    I64(i64),
+    F64(#[cfg_attr(feature = "serde", serde(with = "crate::serde_impl::f64_bits"))] f64),
+}
+
+impl<'a> GGufReader<'a> {
+    /// Reads and decodes a metadata value of type `ty`, nested arrays included.
+    pub fn read_meta_value(&mut self, ty: Ty) -> Result<GGufMetaValue<'a>, GGufReadError> {
+        use GGufMetaValue as V;
+        Ok(match ty {
+            Ty::U8 => V::U8(self.read()?),
+            Ty::I8 => V::I8(self.read()?),
+            Ty::U16 => V::U16(self.read()?),
+            Ty::I16 => V::I16(self.read()?),
+            Ty::U32 => V::U32(self.read()?),
+            Ty::I32 => V::I32(self.read()?),
+            Ty::F32 => V::F32(self.read()?),
+            Ty::Bool => V::Bool(self.read_bool()?),
+            Ty::String => V::String(self.read_str()?.into()),
+            Ty::Array => {
+                let (ty, len) = self.read_arr_header()?;
+                V::Array(
+                    ty,
+                    (0..len)
+                        .map(|_| self.read_meta_value(ty))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            Ty::U64 => V::U64(self.read()?),
+            Ty::I64 => V::I64(self.read()?),
+            Ty::F64 => V::F64(self.read()?),
+        })
+    }
+}
+
+impl GGufMetaValue<'_> {
+    pub const fn ty(&self) -> Ty {
+        match self {
+            Self::U8(_) => Ty::U8,
+            Self::I8(_) => Ty::I8,
+            Self::U16(_) => Ty::U16,
+            Self::I16(_) => Ty::I16,
+            Self::U32(_) => Ty::U32,
+            Self::I32(_) => Ty::I32,
+            Self::F32(_) => Ty::F32,
+            Self::Bool(_) => Ty::Bool,
+            Self::String(_) => Ty::String,
+            Self::Array(..) => Ty::Array,
+            Self::U64(_) => Ty::U64,
+            Self::I64(_) => Ty::I64,
+            Self::F64(_) => Ty::F64,
+        }
+    }
+
+    /// Encodes the value by `writer`.
+    ///
+    /// Fails with [io::ErrorKind::InvalidInput] if an array contains an element of another type.
+    pub fn write_to(&self, writer: &mut GGufWriter<impl Write>) -> io::Result<()> {
+        match self {
+            Self::U8(v) => writer.write(&[*v]),
+            Self::I8(v) => writer.write(&[*v]),
+            Self::U16(v) => writer.write(&[*v]),
+            Self::I16(v) => writer.write(&[*v]),
+            Self::U32(v) => writer.write(&[*v]),
+            Self::I32(v) => writer.write(&[*v]),
+            Self::F32(v) => writer.write(&[*v]),
+            Self::Bool(v) => writer.write(&[*v as u8]),
+            Self::String(v) => writer.write_str(v),
+            Self::Array(ty, vals) => {
+                writer.write(&[*ty])?;
+                writer.write(&[vals.len() as u64])?;
+                for v in vals {
+                    if v.ty() != *ty {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("{:?} element in array of {ty:?}", v.ty()),
+                        ));
+                    }
+                    v.write_to(writer)?
+                }
+                Ok(())
+            }
+            Self::U64(v) => writer.write(&[*v]),
+            Self::I64(v) => writer.write(&[*v]),
+            Self::F64(v) => writer.write(&[*v]),
+        }
+    }
+
+    /// Encodes the value in native byte order and the format of the latest GGUF version.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut ans = Vec::new();
+        self.write_to(&mut GGufWriter::new(&mut ans))?;
+        Ok(ans)
+    }
+
+    #[inline]
+    pub fn to_meta_buf(&self) -> io::Result<GGufMetaBuf<'static>> {
+        Ok(GGufMetaBuf {
+            ty: self.ty(),
+            value: self.to_bytes()?.into(),
+        })
+    }
+}
+
+/// Strings are quoted and escaped, and floats far from 1 are shown in scientific notation.
+///
+/// The precision, if given, limits how many elements of each array are shown.
+impl fmt::Display for GGufMetaValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        macro_rules! float {
+            ($v:expr) => {
+                if $v == 0. {
+                    f.write_str("0")
+                } else if $v.abs().log10().abs() > 3. {
+                    write!(f, "{:e}", $v)
+                } else {
+                    write!(f, "{}", $v)
+                }
+            };
+        }
+
+        match self {
+            Self::U8(v) => write!(f, "{v}"),
+            Self::I8(v) => write!(f, "{v}"),
+            Self::U16(v) => write!(f, "{v}"),
+            Self::I16(v) => write!(f, "{v}"),
+            Self::U32(v) => write!(f, "{v}"),
+            Self::I32(v) => write!(f, "{v}"),
+            Self::F32(v) => float!(*v),
+            Self::Bool(v) => write!(f, "{v}"),
+            Self::String(v) => write!(f, "{v:?}"),
+            Self::Array(_, vals) => {
+                let detail = f.precision();
+                let shown = vals.len().min(detail.unwrap_or(usize::MAX));
+                f.write_str("[")?;
+                for (i, v) in vals[..shown].iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?
+                    }
+                    match detail {
+                        Some(detail) => write!(f, "{v:.detail$}")?,
+                        None => write!(f, "{v}")?,
+                    }
+                }
+                if shown < vals.len() {
+                    if shown > 0 {
+                        f.write_str(", ")?
+                    }
+                    write!(f, "...({} more of {})", vals.len() - shown, vals.len())?
+                }
+                f.write_str("]")
+            }
+            Self::U64(v) => write!(f, "{v}"),
+            Self::I64(v) => write!(f, "{v}"),
+            Self::F64(v) => float!(*v),
+        }
+    }
+}
+
+#[test]
+fn test_meta_value() {
+    use GGufMetaValue as V;
+
+    let val = V::Array(
+        Ty::Array,
+        vec![
+            V::Array(
+                Ty::String,
+                vec![V::String("a".into()), V::String("b\n".into())],
+            ),
+            V::Array(Ty::F32, vec![V::F32(1.5), V::F32(1e-6), V::F32(0.)]),
+            V::Array(Ty::Bool, vec![]),
+        ],
+    );
+    assert_eq!(val.to_string(), r#"[["a", "b\n"], [1.5, 1e-6, 0], []]"#);
+    assert_eq!(
+        format!("{val:.2}"),
+        r#"[["a", "b\n"], [1.5, 1e-6, ...(1 more of 3)], ...(1 more of 3)]"#
+    );
+
+    let bytes = val.to_bytes().unwrap();
+    let decoded = GGufReader::new(&bytes).read_meta_value(Ty::Array).unwrap();
+    assert_eq!(decoded, val);
+
+    let bytes = V::U32(7).to_bytes().unwrap();
+    let decoded = GGufReader::with_endian(&bytes, false)
+        .read_meta_value(Ty::U32)
+        .unwrap();
+    assert_eq!(decoded, V::U32(7u32.swap_bytes()));
+
+    assert!(V::Array(Ty::U8, vec![V::I8(0)]).to_bytes().is_err());
+}
@@ -0,0 +1,288 @@
+use crate::{
+    GGuf, GGufError, GGufFileName, GGufMetaDataValueType, GGufMetaError, GGufMetaKV, GGufMetaMap,
+    GGufMetaMapExt, GGufReader, GGufShardParseError, GGufTensorMeta, GGufTensorView,
+    GENERAL_ALIGNMENT, SPLIT_COUNT, SPLIT_NO, SPLIT_TENSORS_COUNT,
+};
+use indexmap::IndexMap;
+use std::{
+    error::Error,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// A model stored in one or more shards, presented as a single file.
+///
+/// Metadata of all shards is merged, except `general.alignment` and the `split.*` keys,
+/// and each tensor remembers the shard its data lives in.
+pub struct GGufModel<'a> {
+    pub shards: Vec<GGuf<'a>>,
+    pub meta_kvs: IndexMap<&'a str, GGufMetaKV<'a>>,
+    pub tensors: IndexMap<&'a str, GGufModelTensor<'a>>,
+}
+
+/// A tensor of a [GGufModel] and the index of the shard it lives in.
+#[derive(Clone)]
+pub struct GGufModelTensor<'a> {
+    pub shard: usize,
+    pub meta: GGufTensorMeta<'a>,
+}
+
+#[derive(Debug)]
+pub enum GGufModelError {
+    NoShards,
+    /// Failed to parse the shard at the index.
+    Shard(usize, GGufError),
+    /// The shard at the index has a bad `split.*` entry.
+    SplitMeta(usize, &'static str, GGufMetaError),
+    /// The shard at the index is in another byte order or version than the first one.
+    FormatMismatch(usize),
+    ShardCountMismatch {
+        shard: usize,
+        expected: usize,
+        found: usize,
+    },
+    ShardIndexMismatch {
+        shard: usize,
+        found: usize,
+    },
+    TensorCountMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// A metadata key appears in several shards with different values.
+    DuplicateMetaKey(String),
+    DuplicateTensorName(String),
+}
+
+impl fmt::Display for GGufModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoShards => f.write_str("no shards"),
+            Self::Shard(i, e) => write!(f, "shard {i}: {e}"),
+            Self::SplitMeta(i, key, e) => write!(f, "shard {i}: bad {key}: {e:?}"),
+            Self::FormatMismatch(i) => write!(f, "shard {i}: format mismatch"),
+            Self::ShardCountMismatch {
+                shard,
+                expected,
+                found,
+            } => write!(
+                f,
+                "shard {shard}: {SPLIT_COUNT} is {found}, expected {expected}"
+            ),
+            Self::ShardIndexMismatch { shard, found } => {
+                write!(f, "shard {shard}: {SPLIT_NO} is {found}")
+            }
+            Self::TensorCountMismatch { expected, found } => write!(
+                f,
+                "{SPLIT_TENSORS_COUNT} is {expected}, but {found} tensors found"
+            ),
+            Self::DuplicateMetaKey(key) => write!(f, "duplicate meta key: {key}"),
+            Self::DuplicateTensorName(name) => write!(f, "duplicate tensor name: {name}"),
+        }
+    }
+}
+
+impl Error for GGufModelError {}
+
+impl GGufMetaMap for GGufModel<'_> {
+    fn get(&self, key: &str) -> Option<(GGufMetaDataValueType, &[u8])> {
+        self.meta_kvs.get(key).map(|kv| (kv.ty(), kv.value_bytes()))
+    }
+
+    #[inline]
+    fn value_reader<'b>(&self, val: &'b [u8]) -> GGufReader<'b> {
+        self.shards[0].value_reader(val)
+    }
+}
+
+impl<'a> GGufModel<'a> {
+    /// Paths of all shards of the model that `path` is one shard of, in order.
+    pub fn shard_paths(path: &Path) -> Result<Vec<PathBuf>, GGufShardParseError> {
+        let dir = path.parent().unwrap_or(Path::new(""));
+        Ok(GGufFileName::try_from(path)?
+            .iter_all()
+            .map(|name| dir.join(name.to_string()))
+            .collect())
+    }
+
+    /// Parses the shards of a model, which must be given in order.
+    ///
+    /// A single file without `split.*` keys is a model of one shard.
+    pub fn new(shards: impl IntoIterator<Item = &'a [u8]>) -> Result<Self, GGufModelError> {
+        use GGufModelError::*;
+
+        let shards = shards
+            .into_iter()
+            .enumerate()
+            .map(|(i, data)| GGuf::new(data).map_err(|e| Shard(i, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let Some(first) = shards.first() else {
+            return Err(NoShards);
+        };
+
+        let n = shards.len();
+        let mut meta_kvs = IndexMap::new();
+        let mut tensors = IndexMap::new();
+        let mut tensors_count = None;
+        for (i, shard) in shards.iter().enumerate() {
+            if shard.native_endian != first.native_endian
+                || shard.header.version != first.header.version
+            {
+                return Err(FormatMismatch(i));
+            }
+
+            // 单个文件可以没有分片信息
+            let split = |key| match shard.get_usize(key) {
+                Err(GGufMetaError::NotExist) if n == 1 => Ok(None),
+                Ok(val) => Ok(Some(val)),
+                Err(e) => Err(SplitMeta(i, key, e)),
+            };
+            if let Some(found) = split(SPLIT_COUNT)? {
+                if found != n {
+                    return Err(ShardCountMismatch {
+                        shard: i,
+                        expected: n,
+                        found,
+                    });
+                }
+            }
+            if let Some(found) = split(SPLIT_NO)? {
+                if found != i {
+                    return Err(ShardIndexMismatch { shard: i, found });
+                }
+            }
+            match shard.get_usize(SPLIT_TENSORS_COUNT) {
+                Ok(val) => {
+                    let expected = *tensors_count.get_or_insert(val);
+                    if val != expected {
+                        return Err(TensorCountMismatch {
+                            expected,
+                            found: val,
+                        });
+                    }
+                }
+                Err(GGufMetaError::NotExist) => {}
+                Err(e) => return Err(SplitMeta(i, SPLIT_TENSORS_COUNT, e)),
+            }
+
+            for (&k, kv) in &shard.meta_kvs {
+                if k == GENERAL_ALIGNMENT || k.starts_with("split.") {
+                    continue;
+                }
+                match meta_kvs.get(k) {
+                    None => {
+                        meta_kvs.insert(k, kv.clone());
+                    }
+                    Some(prev) if same_value(prev, kv) => {}
+                    Some(_) => return Err(DuplicateMetaKey(k.into())),
+                }
+            }
+            for (&name, meta) in &shard.tensors {
+                let tensor = GGufModelTensor {
+                    shard: i,
+                    meta: meta.clone(),
+                };
+                if tensors.insert(name, tensor).is_some() {
+                    return Err(DuplicateTensorName(name.into()));
+                }
+            }
+        }
+        if let Some(expected) = tensors_count {
+            if expected != tensors.len() {
+                return Err(TensorCountMismatch {
+                    expected,
+                    found: tensors.len(),
+                });
+            }
+        }
+
+        Ok(Self {
+            shards,
+            meta_kvs,
+            tensors,
+        })
+    }
+
+    /// Returns a view of the tensor named `name` in its shard, or `None` if it does not exist.
+    #[inline]
+    pub fn tensor(&self, name: &str) -> Option<GGufTensorView<'a>> {
+        self.shards[self.tensors.get(name)?.shard].tensor(name)
+    }
+}
+
+fn same_value(a: &GGufMetaKV, b: &GGufMetaKV) -> bool {
+    a.ty() == b.ty() && a.value_bytes() == b.value_bytes()
+}
+
+#[cfg(feature = "types")]
+#[test]
+fn test_model() {
+    use crate::{DataFuture, GGmlType, GGufDocument, GGufMetaBuf, GGufTensorBuf};
+
+    let mut doc = GGufDocument::new();
+    doc.insert_meta_kv("general.architecture", GGufMetaBuf::string("llama"));
+    for (i, name) in ["a", "b", "c"].into_iter().enumerate() {
+        doc.insert_tensor(
+            name,
+            GGufTensorBuf {
+                ty: GGmlType::F32,
+                shape: vec![4],
+                data: (0..4)
+                    .flat_map(|x| ((i * 4 + x) as f32).to_ne_bytes())
+                    .collect::<Vec<_>>()
+                    .into(),
+            },
+        );
+    }
+    let shard = |no, range, native_endian| {
+        let mut file = Vec::new();
+        doc.write_shard(&mut file, range, no, 2, native_endian)
+            .unwrap();
+        file
+    };
+
+    let a = shard(0, 0..1, true);
+    let b = shard(1, 1..3, true);
+    let model = GGufModel::new([&*a, &*b]).unwrap();
+    assert_eq!(model.general_architecture().unwrap(), "llama");
+    assert!(!model.meta_kvs.contains_key(SPLIT_NO));
+    assert_eq!(
+        model.tensors.keys().copied().collect::<Vec<_>>(),
+        ["a", "b", "c"]
+    );
+    assert_eq!(model.tensors["c"].shard, 1);
+    assert_eq!(
+        model.tensor("c").unwrap().as_f32().unwrap(),
+        [8., 9., 10., 11.]
+    );
+
+    assert!(matches!(
+        GGufModel::new([&*b, &*a]),
+        Err(GGufModelError::ShardIndexMismatch { shard: 0, found: 1 })
+    ));
+    assert!(matches!(
+        GGufModel::new([&*a]),
+        Err(GGufModelError::ShardCountMismatch { .. })
+    ));
+
+    // 非本机字节序的分片
+    let a = shard(0, 0..1, false);
+    let b = shard(1, 1..3, false);
+    let model = GGufModel::new([&*a, &*b]).unwrap();
+    assert!(matches!(
+        model.get_usize(SPLIT_COUNT),
+        Err(GGufMetaError::NotExist)
+    ));
+    assert_eq!(model.shards[1].get_usize(SPLIT_TENSORS_COUNT).unwrap(), 3);
+    let mut data = model.tensor("b").unwrap().data().to_vec();
+    GGmlType::F32.swap_bytes(&mut data).unwrap();
+    assert_eq!(data, doc.tensors["b"].data.get());
+
+    assert_eq!(
+        GGufModel::shard_paths(Path::new("dir/Tiny-1M-v1.0-F32-00001-of-00002.gguf")).unwrap(),
+        [
+            Path::new("dir/Tiny-1M-v1.0-F32-00001-of-00002.gguf"),
+            Path::new("dir/Tiny-1M-v1.0-F32-00002-of-00002.gguf"),
+        ]
+    );
+}
@@ -0,0 +1,166 @@
+use super::{r#type::Type, size_label::SizeLabel, version::Version, GGufFileName};
+use crate::{GGmlType, GGufMetaMap, GGufMetaMapExt};
+use std::{borrow::Cow, collections::HashMap};
+
+impl<'a> GGufFileName<'a> {
+    /// Builds the canonical file name of a model from its metadata and tensors.
+    ///
+    /// The base name comes from `general.basename`, or `general.name` if it is missing.
+    /// If `general.size_label` is missing, the size label is computed from the numbers of parameters,
+    /// in the `8x7B` form for mixtures of experts. The encoding is the type holding most parameters.
+    pub fn from_meta<M, N, S>(
+        meta: &'a M,
+        tensors: impl IntoIterator<Item = (N, GGmlType, S)>,
+    ) -> Self
+    where
+        M: GGufMetaMap,
+        N: AsRef<str>,
+        S: AsRef<[u64]>,
+    {
+        let mut params = Params::default();
+        let mut types = HashMap::<GGmlType, u64>::new();
+        let mut n_tensors = 0;
+        for (name, ty, shape) in tensors {
+            let shape = shape.as_ref();
+            let n = shape.iter().product::<u64>();
+            params.add(name.as_ref(), shape, n);
+            *types.entry(ty).or_default() += n;
+            n_tensors += 1
+        }
+
+        let size_label = meta
+            .general_size_label()
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .or_else(|| params.size_label());
+        let version = meta.general_version().ok().and_then(|v| {
+            if v.starts_with('v') {
+                v.parse().ok()
+            } else {
+                format!("v{v}").parse::<Version>().ok()
+            }
+        });
+        let encoding = types
+            .into_iter()
+            .max_by_key(|&(ty, n)| (n, ty as u32))
+            .map(|(ty, _)| ty.name().to_uppercase().into());
+        let type_ = if meta.get_str("general.type").is_ok_and(|t| t == "adapter") {
+            Type::LoRA
+        } else if n_tensors == 0 {
+            Type::Vocab
+        } else {
+            Type::Default
+        };
+
+        Self {
+            base_name: meta
+                .general_basename()
+                .or_else(|_| meta.general_name())
+                .map_or("ggml-model".into(), normalize),
+            size_label,
+            fine_tune: meta.general_finetune().ok().map(normalize),
+            version,
+            encoding,
+            type_,
+            shard: Default::default(),
+        }
+    }
+}
+
+/// 名字中的空格和斜杠替换为连字符
+fn normalize(s: &str) -> Cow<'_, str> {
+    let s = s.trim();
+    if s.contains([' ', '/']) {
+        s.replace([' ', '/'], "-").into()
+    } else {
+        s.into()
+    }
+}
+
+#[derive(Default)]
+struct Params {
+    shared: u64,
+    expert: u64,
+    expert_sum: u64,
+    expert_tensors: u64,
+}
+
+impl Params {
+    fn add(&mut self, name: &str, shape: &[u64], n: u64) {
+        // 合并存储的专家张量，最高维是专家数
+        match shape.last() {
+            Some(&count) if name.contains("_exps.") && count > 0 => {
+                self.expert += n / count;
+                self.expert_sum += count;
+                self.expert_tensors += 1
+            }
+            _ => self.shared += n,
+        }
+    }
+
+    fn size_label(&self) -> Option<SizeLabel> {
+        let label = if let Some(count) = self.expert_sum.checked_div(self.expert_tensors) {
+            format!("{count}x{}", rounded(self.shared + self.expert))
+        } else if self.shared > 0 {
+            rounded(self.shared)
+        } else {
+            return None;
+        };
+        label.parse().ok()
+    }
+}
+
+/// 以两位有效数字表示参数量
+fn rounded(n: u64) -> String {
+    let n = n as f64;
+    let (scaled, suffix) = if n > 1e12 {
+        (n / 1e12, 'T')
+    } else if n > 1e9 {
+        (n / 1e9, 'B')
+    } else if n > 1e6 {
+        (n / 1e6, 'M')
+    } else {
+        (n / 1e3, 'K')
+    };
+    let fix = 2usize.saturating_sub((scaled.round() as u64).to_string().len());
+    format!("{scaled:.fix$}{suffix}")
+}
+
+#[test]
+fn test_from_meta() {
+    use crate::{GGufDocument, GGufMetaBuf};
+
+    let mut doc = GGufDocument::new();
+    doc.insert_meta_kv("general.basename", GGufMetaBuf::string("Tiny Llama"));
+    doc.insert_meta_kv("general.finetune", GGufMetaBuf::string("Chat"));
+    doc.insert_meta_kv("general.version", GGufMetaBuf::string("1.0"));
+
+    let dense = [
+        ("token_embd.weight", GGmlType::F16, vec![2048, 1000]),
+        ("blk.0.attn_q.weight", GGmlType::Q4K, vec![2048, 2048]),
+        ("blk.0.ffn_up.weight", GGmlType::Q4K, vec![2048, 5632]),
+        ("output_norm.weight", GGmlType::F32, vec![2048]),
+    ];
+    let name = GGufFileName::from_meta(&doc, dense.iter().map(|(n, t, s)| (n, *t, s)));
+    assert_eq!(name.to_string(), "Tiny-Llama-18M-Chat-v1.0-Q4_K.gguf");
+
+    doc.insert_meta_kv("general.size_label", GGufMetaBuf::string("1.1B"));
+    let name = GGufFileName::from_meta(&doc, dense.iter().map(|(n, t, s)| (n, *t, s)));
+    assert_eq!(name.to_string(), "Tiny-Llama-1.1B-Chat-v1.0-Q4_K.gguf");
+
+    let moe = [
+        ("blk.0.attn_q.weight", GGmlType::Q8_0, vec![4096, 4096]),
+        (
+            "blk.0.ffn_up_exps.weight",
+            GGmlType::Q8_0,
+            vec![4096, 14336, 8],
+        ),
+    ];
+    let mut doc = GGufDocument::new();
+    doc.insert_meta_kv("general.name", GGufMetaBuf::string("Mixtral"));
+    let name = GGufFileName::from_meta(&doc, moe.iter().map(|(n, t, s)| (n, *t, s)));
+    assert_eq!(name.to_string(), "Mixtral-8x75M-Q8_0.gguf");
+
+    let name = GGufFileName::from_meta(&doc, [] as [(&str, GGmlType, &[u64]); 0]);
+    assert_eq!(name.to_string(), "Mixtral-vocab.gguf");
+}
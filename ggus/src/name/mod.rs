@@ -1,4 +1,5 @@
-﻿mod shard;
+﻿mod meta;
+mod shard;
 mod size_label;
 mod r#type;
 mod version;
@@ -7,7 +8,7 @@ use fancy_regex::{Captures, Regex};
 use r#type::Type;
 use shard::Shard;
 use size_label::SizeLabel;
-use std::{borrow::Cow, fmt, num::NonZero, path::Path, sync::LazyLock};
+use std::{borrow::Cow, error::Error, fmt, num::NonZero, path::Path, sync::LazyLock};
 use version::Version;
 
 #[derive(Clone, Debug)]
@@ -21,30 +22,64 @@ pub struct GGufFileName<'a> {
     pub shard: Shard,
 }
 
-#[derive(Debug)]
-pub struct GGufShardParseError;
+/// Why a file name cannot be parsed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum GGufShardParseError {
+    /// The path has no file name, or it is not valid UTF-8.
+    InvalidPath,
+    /// The file name does not end with `.gguf`.
+    NotGGuf(String),
+    SizeLabel(String),
+    Version(String),
+    /// The shard suffix is malformed, zero, or the index is greater than the count.
+    Shard(String),
+}
+
+impl fmt::Display for GGufShardParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPath => f.write_str("invalid path"),
+            Self::NotGGuf(name) => write!(f, "not a gguf file name: {name}"),
+            Self::SizeLabel(s) => write!(f, "invalid size label: {s}"),
+            Self::Version(s) => write!(f, "invalid version: {s}"),
+            Self::Shard(s) => write!(f, "invalid shard: {s}"),
+        }
+    }
+}
+
+impl Error for GGufShardParseError {}
 
 impl<'a> TryFrom<&'a str> for GGufFileName<'a> {
     type Error = GGufShardParseError;
 
+    /// Parses a file name following the GGUF naming convention.
+    ///
+    /// Names that do not follow the convention are parsed leniently:
+    /// all but the `-NNNNN-of-NNNNN` shard suffix is taken as the base name.
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        let captures = match_name(value);
+        let Some(captures) = match_name(value) else {
+            return parse_lenient(value);
+        };
         Ok(Self {
             base_name: captures.name("BaseName").unwrap().as_str().into(),
             size_label: captures
                 .name("SizeLabel")
-                .map(|m| m.as_str().parse().unwrap()),
+                .map(|m| m.as_str().parse())
+                .transpose()?,
             fine_tune: captures.name("FineTune").map(|m| m.as_str().into()),
             version: captures
                 .name("Version")
-                .map(|m| m.as_str().parse().unwrap()),
+                .map(|m| m.as_str().parse())
+                .transpose()?,
             encoding: captures.name("Encoding").map(|m| m.as_str().into()),
             type_: captures
                 .name("Type")
                 .map_or(Type::Default, |m| m.as_str().parse().unwrap()),
             shard: captures
                 .name("Shard")
-                .map_or(Default::default(), |m| m.as_str().parse().unwrap()),
+                .map(|m| m.as_str().parse())
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
@@ -53,7 +88,11 @@ impl<'a> TryFrom<&'a Path> for GGufFileName<'a> {
     type Error = GGufShardParseError;
     #[inline]
     fn try_from(value: &'a Path) -> Result<Self, Self::Error> {
-        Self::try_from(value.file_name().unwrap().to_str().unwrap())
+        value
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or(GGufShardParseError::InvalidPath)
+            .and_then(Self::try_from)
     }
 }
 
@@ -135,15 +174,74 @@ impl fmt::Display for GGufFileName<'_> {
     }
 }
 
-fn match_name(value: &str) -> Captures {
+fn match_name(value: &str) -> Option<Captures<'_>> {
     // See: <https://github.com/ggerganov/ggml/blob/master/docs/gguf.md#validating-above-naming-convention>
     const PATTERN: &str = r"^(?<BaseName>[A-Za-z0-9\s]*(?:(?:-(?:(?:[A-Za-z\s][A-Za-z0-9\s]*)|(?:[0-9\s]*)))*))-(?:(?<SizeLabel>(?:\d+x)?(?:\d+\.)?\d+[A-Za-z](?:-[A-Za-z]+(\d+\.)?\d+[A-Za-z]+)?)(?:-(?<FineTune>[A-Za-z0-9\s-]+))?)?-(?:(?<Version>v\d+(?:\.\d+)*))(?:-(?<Encoding>(?!LoRA|vocab)[\w_]+))?(?:-(?<Type>LoRA|vocab))?(?:-(?<Shard>\d{5}-of-\d{5}))?\.gguf$";
     static REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(PATTERN).unwrap());
 
-    REGEX.captures(value).unwrap().unwrap()
+    REGEX.captures(value).ok().flatten()
+}
+
+fn parse_lenient(value: &str) -> Result<GGufFileName<'_>, GGufShardParseError> {
+    let name = value
+        .strip_suffix(".gguf")
+        .ok_or_else(|| GGufShardParseError::NotGGuf(value.into()))?;
+
+    // 形如 -NNNNN-of-NNNNN 的后缀
+    const SHARD_LEN: usize = "00001-of-00001".len();
+    let (base_name, shard) = match name.len().checked_sub(SHARD_LEN + 1) {
+        Some(i) if name.is_char_boundary(i) && name[i..].starts_with('-') => {
+            match name[i + 1..].parse() {
+                Ok(shard) => (&name[..i], shard),
+                Err(_) => (name, Default::default()),
+            }
+        }
+        _ => (name, Default::default()),
+    };
+    Ok(GGufFileName {
+        base_name: base_name.into(),
+        size_label: None,
+        fine_tune: None,
+        version: None,
+        encoding: None,
+        type_: Type::Default,
+        shard,
+    })
 }
 
 #[test]
 fn test_name() {
-    match_name("MiniCPM3-1B-sft-v0.0-F16.gguf");
+    fn round_trip(name: &str) -> GGufFileName<'_> {
+        let ans = GGufFileName::try_from(name).unwrap();
+        assert_eq!(ans.to_string(), name);
+        ans
+    }
+
+    round_trip("MiniCPM3-1B-sft-v0.0-F16.gguf");
+    round_trip("Qwen2-1.05B-v1.0-F16.gguf");
+    round_trip("Mixtral-8x7B-v0.1-Q2_K-00002-of-00003.gguf");
+
+    let name = round_trip("llama-7b-v1.0.1-Q4_K_M.gguf");
+    assert_eq!(name.version.unwrap().components(), [1, 0, 1]);
+
+    let name = round_trip("Qwen2-1.5B-v1.05-F16.gguf");
+    assert_eq!(name.version.unwrap().components(), [1, 5]);
+
+    let name = round_trip("model.gguf");
+    assert_eq!(name.base_name, "model");
+    assert_eq!(name.shard_count(), 1);
+
+    let name = round_trip("my_model-00002-of-00003.gguf");
+    assert_eq!(name.base_name, "my_model");
+    assert_eq!(name.shard.index.get(), 2);
+    assert_eq!(name.shard_count(), 3);
+
+    assert!(matches!(
+        GGufFileName::try_from("model.bin"),
+        Err(GGufShardParseError::NotGGuf(_))
+    ));
+    assert!(matches!(
+        GGufFileName::try_from("Tiny-1M-v1.0-F32-00004-of-00003.gguf"),
+        Err(GGufShardParseError::Shard(_))
+    ));
 }
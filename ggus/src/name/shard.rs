@@ -1,4 +1,5 @@
-﻿use fancy_regex::Regex;
+﻿use super::GGufShardParseError;
+use fancy_regex::Regex;
 use std::{fmt, num::NonZero, str::FromStr, sync::LazyLock};
 
 #[derive(Clone, Debug)]
@@ -17,17 +18,20 @@ impl Default for Shard {
 }
 
 impl FromStr for Shard {
-    type Err = ();
+    type Err = GGufShardParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         const PATTERN: &str = r"^(\d{5})-of-(\d{5})$";
         static REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(PATTERN).unwrap());
 
-        let captures = REGEX.captures(s).unwrap().unwrap();
-        Ok(Self {
-            index: captures.get(1).unwrap().as_str().parse().unwrap(),
-            count: captures.get(2).unwrap().as_str().parse().unwrap(),
-        })
+        let err = || GGufShardParseError::Shard(s.into());
+        let captures = REGEX.captures(s).ok().flatten().ok_or_else(err)?;
+        let index = captures[1].parse::<NonZero<u32>>().map_err(|_| err())?;
+        let count = captures[2].parse::<NonZero<u32>>().map_err(|_| err())?;
+        if index > count {
+            return Err(err());
+        }
+        Ok(Self { index, count })
     }
 }
 
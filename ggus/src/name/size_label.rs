@@ -1,45 +1,49 @@
-﻿use fancy_regex::Regex;
+﻿use super::GGufShardParseError;
+use fancy_regex::Regex;
 use std::{fmt, str::FromStr, sync::LazyLock};
 
+/// A size label like `7B`, `8x7B`, `1.5B` or `16x3.8B-A6.6B`.
 #[derive(Clone, PartialEq, Debug)]
 pub struct SizeLabel {
     e: u32,
-    a: u32,
-    b: u32,
+    /// 数值保留原文，以免丢失小数部分的前导 0
+    n: String,
     l: char,
+    suffix: Option<String>,
 }
 
 impl FromStr for SizeLabel {
-    type Err = ();
+    type Err = GGufShardParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        const PATTERN: &str = r"^(\d+x)?(\d+)(\.\d+)?([QTBMK])$";
+        const PATTERN: &str =
+            r"^(?:(\d+)x)?(\d+(?:\.\d+)?)([A-Za-z])(-[A-Za-z]+(?:\d+\.)?\d+[A-Za-z]+)?$";
         static REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(PATTERN).unwrap());
 
-        let captures = REGEX.captures(s).unwrap().unwrap();
+        let err = || GGufShardParseError::SizeLabel(s.into());
+        let captures = REGEX.captures(s).ok().flatten().ok_or_else(err)?;
         Ok(Self {
-            e: captures.get(1).map_or(0, |m| {
-                m.as_str().strip_suffix('x').unwrap().parse().unwrap()
-            }),
-            a: captures.get(2).unwrap().as_str().parse().unwrap(),
-            b: captures.get(3).map_or(0, |m| {
-                m.as_str().strip_prefix('.').unwrap().parse().unwrap()
-            }),
-            l: captures.get(4).unwrap().as_str().chars().next().unwrap(),
+            e: match captures.get(1) {
+                Some(m) => m.as_str().parse().map_err(|_| err())?,
+                None => 0,
+            },
+            n: captures[2].into(),
+            l: captures[3].chars().next().unwrap(),
+            suffix: captures.get(4).map(|m| m.as_str().into()),
         })
     }
 }
 
 impl fmt::Display for SizeLabel {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let &Self { e, a, b, l } = self;
-        match e {
-            0 => {}
-            _ => write!(f, "{e}x")?,
+        let Self { e, n, l, suffix } = self;
+        if *e != 0 {
+            write!(f, "{e}x")?
         }
-        match b {
-            0 => write!(f, "{a}{l}"),
-            _ => write!(f, "{a}.{b}{l}"),
+        write!(f, "{n}{l}")?;
+        if let Some(suffix) = suffix {
+            f.write_str(suffix)?
         }
+        Ok(())
     }
 }
@@ -1,30 +1,47 @@
-﻿use fancy_regex::Regex;
-use std::{fmt, str::FromStr, sync::LazyLock};
+﻿use super::GGufShardParseError;
+use std::{fmt, str::FromStr};
 
-#[derive(Clone, Debug)]
+/// A version like `v1.0` or `v1.0.1`, with at least one component.
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Version {
-    major: u32,
-    minor: u32,
+    /// 保留原文，以免丢失各部分的前导 0
+    text: String,
+    components: Vec<u32>,
+}
+
+impl Version {
+    #[inline]
+    pub fn components(&self) -> &[u32] {
+        &self.components
+    }
 }
 
 impl FromStr for Version {
-    type Err = ();
+    type Err = GGufShardParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        const PATTERN: &str = r"^v(\d+)\.(\d+)$";
-        static REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(PATTERN).unwrap());
-
-        let captures = REGEX.captures(s).unwrap().unwrap();
+        let err = || GGufShardParseError::Version(s.into());
+        let components = s
+            .strip_prefix('v')
+            .ok_or_else(err)?
+            .split('.')
+            .map(|c| {
+                if !c.is_empty() && c.bytes().all(|b| b.is_ascii_digit()) {
+                    c.parse().map_err(|_| err())
+                } else {
+                    Err(err())
+                }
+            })
+            .collect::<Result<_, _>>()?;
         Ok(Self {
-            major: captures.get(1).unwrap().as_str().parse().unwrap(),
-            minor: captures.get(2).unwrap().as_str().parse().unwrap(),
+            text: s.into(),
+            components,
         })
     }
 }
 
 impl fmt::Display for Version {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let &Self { major, minor } = self;
-        write!(f, "v{major}.{minor}")
+        f.write_str(&self.text)
     }
 }
@@ -0,0 +1,202 @@
+use crate::{
+    metadata::is_layout_key, pad, GGuf, GGufError, GGufFileHeader, GGufMetaBuf,
+    GGufMetaDataValueType as Ty, GGufWriter, GENERAL_PADDING,
+};
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result, Seek, SeekFrom, Write},
+};
+
+/// A plan to replace the metadata of a file in place, leaving tensor data untouched.
+///
+/// The new header, metadata and tensor infos are written over the old ones.
+/// They must fit in the space before tensor data, and the rest of the space is taken by
+/// alignment padding or, if that is not enough, by a `general.padding` entry.
+pub struct GGufMetaPatch {
+    /// Bytes available before tensor data.
+    pub budget: usize,
+    /// Bytes needed by the header, metadata and tensor infos without any padding.
+    pub required: usize,
+    /// Bytes to be written at the start of the file, or `None` if they do not fit.
+    bytes: Option<Vec<u8>>,
+    padding_kv: bool,
+}
+
+impl GGufMetaPatch {
+    /// Plans to replace the metadata of `file` with `meta_kvs`, which are in native byte order and the latest format.
+    ///
+    /// `general.alignment` and the shard keys `split.*` of the file are kept,
+    /// and `general.alignment`, `general.padding` and `split.*` in `meta_kvs` are ignored.
+    /// The patched file is always of the latest version, in the byte order of the original file.
+    pub fn new<'b, K: AsRef<str>>(
+        file: &[u8],
+        meta_kvs: impl IntoIterator<Item = (K, &'b GGufMetaBuf<'b>)>,
+    ) -> std::result::Result<Self, GGufError> {
+        let gguf = GGuf::new(file)?;
+        let budget = file.len() - gguf.data.len();
+        let meta_kvs = meta_kvs
+            .into_iter()
+            .filter(|(k, _)| !is_layout_key(k.as_ref()))
+            .map(|(k, v)| {
+                let v = if gguf.native_endian {
+                    v.clone()
+                } else {
+                    v.swap_bytes(true)
+                };
+                (k, v)
+            })
+            .collect::<Vec<_>>();
+
+        // 文件自身的对齐和分片信息原样保留
+        let layout_kvs = gguf
+            .meta_kvs
+            .iter()
+            .filter(|(&k, _)| k != GENERAL_PADDING && is_layout_key(k))
+            .collect::<Vec<_>>();
+
+        let build = |padding: Option<usize>| {
+            let n_meta_kvs = layout_kvs.len() + meta_kvs.len() + padding.is_some() as usize;
+            let header = GGufFileHeader::new(3, gguf.tensors.len() as _, n_meta_kvs as _);
+
+            let mut bytes = Vec::with_capacity(budget);
+            let mut writer = GGufWriter::with_endian(&mut bytes, gguf.native_endian);
+            writer.write_header(header).unwrap();
+            for (&k, kv) in &layout_kvs {
+                writer.write_meta_kv(k, kv.ty(), kv.value_bytes()).unwrap();
+            }
+            for (k, v) in &meta_kvs {
+                writer.write_meta_kv(k.as_ref(), v.ty, &v.value).unwrap();
+            }
+            if let Some(len) = padding {
+                writer.write_str(GENERAL_PADDING).unwrap();
+                writer.write(&[Ty::Array, Ty::U8]).unwrap();
+                writer.write(&[len as u64]).unwrap();
+                writer.write(&vec![0u8; len]).unwrap();
+            }
+            for (&name, meta) in &gguf.tensors {
+                let info = meta.to_info();
+                writer
+                    .write_tensor_info(name, info.shape(), info.ty(), info.offset() as _)
+                    .unwrap();
+            }
+            drop(writer);
+            bytes
+        };
+
+        // 先尝试仅用对齐填充，再尝试插入填充元信息
+        let mut bytes = build(None);
+        let required = bytes.len();
+        let (bytes, padding_kv) =
+            if required <= budget && required + pad(required, gguf.alignment) == budget {
+                bytes.resize(budget, 0);
+                (Some(bytes), false)
+            } else if required + padding_kv_overhead() <= budget {
+                let bytes = build(Some(budget - required - padding_kv_overhead()));
+                debug_assert_eq!(bytes.len(), budget);
+                (Some(bytes), true)
+            } else {
+                (None, false)
+            };
+
+        Ok(Self {
+            budget,
+            required,
+            bytes,
+            padding_kv,
+        })
+    }
+
+    /// Whether the new metadata fits in the file.
+    #[inline]
+    pub fn fits(&self) -> bool {
+        self.bytes.is_some()
+    }
+
+    /// Whether a `general.padding` entry is needed to keep tensor data in place.
+    #[inline]
+    pub fn uses_padding_kv(&self) -> bool {
+        self.padding_kv
+    }
+
+    /// Writes the patch at the start of `writer`, which should be the file the plan is made for.
+    pub fn write_to<T: Write + Seek>(&self, mut writer: T) -> Result<()> {
+        let Some(bytes) = &self.bytes else {
+            return Err(Error::new(ErrorKind::InvalidInput, self.to_string()));
+        };
+        writer.seek(SeekFrom::Start(0))?;
+        writer.write_all(bytes)?;
+        writer.flush()
+    }
+}
+
+impl fmt::Display for GGufMetaPatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            budget, required, ..
+        } = self;
+        write!(
+            f,
+            "{required} of {budget} bytes before tensor data needed, "
+        )?;
+        if !self.fits() {
+            write!(f, "does not fit")
+        } else if self.padding_kv {
+            write!(f, "fits with {GENERAL_PADDING}")
+        } else {
+            write!(f, "fits")
+        }
+    }
+}
+
+/// 填充元信息除数据外的字节数：键、值类型、元素类型和数组长度
+const fn padding_kv_overhead() -> usize {
+    size_of::<u64>() + GENERAL_PADDING.len() + 2 * size_of::<Ty>() + size_of::<u64>()
+}
+
+#[test]
+fn test_patch() {
+    use crate::{DataFuture, GGmlType, GGufDocument, GGufMetaMapExt, GGufTensorBuf};
+    use std::io::Cursor;
+
+    let mut doc = GGufDocument::new();
+    doc.insert_meta_kv("general.name", GGufMetaBuf::string(&"x".repeat(64)));
+    doc.insert_tensor(
+        "a",
+        GGufTensorBuf {
+            ty: GGmlType::F32,
+            shape: vec![4],
+            data: vec![1; 16].into(),
+        },
+    );
+    let mut file = Vec::new();
+    doc.write(&mut file).unwrap();
+    let len = file.len();
+    let data = doc.tensors["a"].data.get().to_vec();
+
+    // 更短的值留下的空间需要填充
+    doc.insert_meta_kv("general.name", GGufMetaBuf::string("t"));
+    let patch = GGufMetaPatch::new(&file, &doc.meta_kvs).unwrap();
+    assert_eq!((patch.required, patch.budget), (123, 192));
+    assert!(patch.fits() && patch.uses_padding_kv());
+    patch.write_to(Cursor::new(&mut file)).unwrap();
+    let gguf = GGuf::new(&file).unwrap();
+    assert_eq!(gguf.get_str("general.name").unwrap(), "t");
+    assert_eq!(gguf.data, data);
+
+    // 填充元信息被替换，对齐填充足够容纳更长的值
+    doc.insert_meta_kv("general.name", GGufMetaBuf::string(&"x".repeat(66)));
+    let patch = GGufMetaPatch::new(&file, &doc.meta_kvs).unwrap();
+    assert!(patch.fits() && !patch.uses_padding_kv());
+    patch.write_to(Cursor::new(&mut file)).unwrap();
+    let gguf = GGuf::new(&file).unwrap();
+    assert_eq!(gguf.get_str("general.name").unwrap(), "x".repeat(66));
+    assert!(!gguf.meta_kvs.contains_key(GENERAL_PADDING));
+    assert_eq!(gguf.data, data);
+
+    // 空间不足
+    doc.insert_meta_kv("general.name", GGufMetaBuf::string(&"x".repeat(80)));
+    let patch = GGufMetaPatch::new(&file, &doc.meta_kvs).unwrap();
+    assert!(!patch.fits());
+    assert!(patch.write_to(Cursor::new(&mut file)).is_err());
+    assert_eq!(file.len(), len);
+}
@@ -1,12 +1,17 @@
 ﻿use crate::metadata::GGufMetaDataValueType;
 use std::{
     alloc::Layout,
+    mem::MaybeUninit,
+    slice::from_raw_parts_mut,
     str::{from_utf8, from_utf8_unchecked, Utf8Error},
 };
 
 #[derive(Clone)]
-#[repr(transparent)]
-pub struct GGufReader<'a>(&'a [u8]);
+pub struct GGufReader<'a> {
+    data: &'a [u8],
+    native_endian: bool,
+    version: u32,
+}
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum GGufReadError {
@@ -18,30 +23,83 @@ pub enum GGufReadError {
 impl<'a> GGufReader<'a> {
     #[inline]
     pub const fn new(data: &'a [u8]) -> Self {
-        Self(data)
+        Self::with_endian(data, true)
+    }
+
+    /// Creates a reader over data in native byte order if `native_endian` is true,
+    /// or in the opposite byte order otherwise.
+    #[inline]
+    pub const fn with_endian(data: &'a [u8], native_endian: bool) -> Self {
+        Self::with_format(data, native_endian, 3)
+    }
+
+    /// Creates a reader over data in the given byte order and GGUF version.
+    #[inline]
+    pub const fn with_format(data: &'a [u8], native_endian: bool, version: u32) -> Self {
+        Self {
+            data,
+            native_endian,
+            version,
+        }
+    }
+
+    /// Creates a reader over another data in the same format.
+    #[inline]
+    pub(crate) const fn with_data<'b>(&self, data: &'b [u8]) -> GGufReader<'b> {
+        GGufReader::with_format(data, self.native_endian, self.version)
+    }
+
+    #[inline]
+    pub const fn is_native_endian(&self) -> bool {
+        self.native_endian
+    }
+
+    #[inline]
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    #[inline]
+    pub(crate) fn set_format(&mut self, native_endian: bool, version: u32) {
+        self.native_endian = native_endian;
+        self.version = version
     }
 
     #[inline]
     pub const fn remaining(&self) -> &'a [u8] {
-        self.0
+        self.data
     }
 
     pub(crate) fn skip<T>(&mut self, len: usize) -> Result<&mut Self, GGufReadError> {
         let len = Layout::array::<T>(len).unwrap().size();
-        let (_, tail) = self.0.split_at_checked(len).ok_or(GGufReadError::Eos)?;
-        self.0 = tail;
+        let (_, tail) = self.data.split_at_checked(len).ok_or(GGufReadError::Eos)?;
+        self.data = tail;
         Ok(self)
     }
 
     pub(crate) fn skip_str(&mut self) -> Result<&mut Self, GGufReadError> {
-        let len = self.read::<u64>()?;
+        let len = self.read_size()?;
         self.skip::<u8>(len as _)
     }
 
+    /// Reads a size (length of string or array, count or dimension), which is u32 in GGUF v1 and u64 since v2.
+    pub fn read_size(&mut self) -> Result<u64, GGufReadError> {
+        if self.version == 1 {
+            self.read::<u32>().map(u64::from)
+        } else {
+            self.read::<u64>()
+        }
+    }
+
+    /// Reads a scalar value, multi-byte values are byte-swapped as a whole if the data is not in native byte order.
     pub fn read<T: Copy>(&mut self) -> Result<T, GGufReadError> {
-        let ptr = self.0.as_ptr().cast::<T>();
+        let ptr = self.data.as_ptr().cast::<MaybeUninit<T>>();
         self.skip::<T>(1)?;
-        Ok(unsafe { ptr.read_unaligned() })
+        let mut val = unsafe { ptr.read_unaligned() };
+        if !self.native_endian {
+            unsafe { from_raw_parts_mut(val.as_mut_ptr().cast::<u8>(), size_of::<T>()) }.reverse()
+        }
+        Ok(unsafe { val.assume_init() })
     }
 
     pub fn read_bool(&mut self) -> Result<bool, GGufReadError> {
@@ -53,10 +111,10 @@ impl<'a> GGufReader<'a> {
     }
 
     pub fn read_str(&mut self) -> Result<&'a str, GGufReadError> {
-        let len = self.read::<u64>()? as _;
-        let (s, tail) = self.0.split_at_checked(len).ok_or(GGufReadError::Eos)?;
+        let len = self.read_size()? as _;
+        let (s, tail) = self.data.split_at_checked(len).ok_or(GGufReadError::Eos)?;
         let ans = from_utf8(s).map_err(GGufReadError::Utf8)?;
-        self.0 = tail;
+        self.data = tail;
         Ok(ans)
     }
 
@@ -66,13 +124,13 @@ impl<'a> GGufReader<'a> {
     ///
     /// This function does not check if the data is valid utf8.
     pub unsafe fn read_str_unchecked(&mut self) -> &'a str {
-        let len = self.read::<u64>().unwrap() as _;
-        let (s, tail) = self.0.split_at(len);
-        self.0 = tail;
+        let len = self.read_size().unwrap() as _;
+        let (s, tail) = self.data.split_at(len);
+        self.data = tail;
         from_utf8_unchecked(s)
     }
 
     pub fn read_arr_header(&mut self) -> Result<(GGufMetaDataValueType, usize), GGufReadError> {
-        Ok((self.read()?, self.read::<u64>()? as _))
+        Ok((self.read()?, self.read_size()? as _))
     }
 }
@@ -0,0 +1,212 @@
+use crate::{GGmlType, GGufFileType, GGufMetaBuf, GGufMetaValue, GGufReader, GGufTensorInfo};
+use num_enum::TryFromPrimitive;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+// 包含废弃变体的枚举不能直接派生，按名字序列化
+macro_rules! by_name {
+    ($ty:ty) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&format!("{self:?}"))
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let name = String::deserialize(deserializer)?;
+                (0..u8::MAX as u32)
+                    .filter_map(|i| <$ty>::try_from_primitive(i).ok())
+                    .find(|ty| format!("{ty:?}") == name)
+                    .ok_or_else(|| de::Error::custom(format!("unknown variant {name}")))
+            }
+        }
+    };
+}
+
+by_name!(GGmlType);
+by_name!(GGufFileType);
+
+// JSON 数字不能表示 NaN 和 ±inf，可读格式中非有限值按位模式存为字符串
+macro_rules! float_bits {
+    ($name:ident, $ty:ty, $bits:ty) => {
+        pub(crate) mod $name {
+            use serde::{de, Deserializer, Serializer};
+            use std::fmt;
+
+            pub fn serialize<S: Serializer>(val: &$ty, serializer: S) -> Result<S::Ok, S::Error> {
+                if val.is_finite() || !serializer.is_human_readable() {
+                    serde::Serialize::serialize(val, serializer)
+                } else {
+                    serializer.serialize_str(&format!("{:#x}", val.to_bits()))
+                }
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<$ty, D::Error> {
+                struct Visitor;
+                impl de::Visitor<'_> for Visitor {
+                    type Value = $ty;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str(concat!("a number or the hex bits of ", stringify!($ty)))
+                    }
+
+                    fn visit_f64<E: de::Error>(self, v: f64) -> Result<$ty, E> {
+                        Ok(v as _)
+                    }
+
+                    fn visit_f32<E: de::Error>(self, v: f32) -> Result<$ty, E> {
+                        Ok(v as _)
+                    }
+
+                    fn visit_i64<E: de::Error>(self, v: i64) -> Result<$ty, E> {
+                        Ok(v as _)
+                    }
+
+                    fn visit_u64<E: de::Error>(self, v: u64) -> Result<$ty, E> {
+                        Ok(v as _)
+                    }
+
+                    fn visit_str<E: de::Error>(self, v: &str) -> Result<$ty, E> {
+                        v.strip_prefix("0x")
+                            .and_then(|hex| <$bits>::from_str_radix(hex, 16).ok())
+                            .map(<$ty>::from_bits)
+                            .ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+                    }
+                }
+
+                if deserializer.is_human_readable() {
+                    deserializer.deserialize_any(Visitor)
+                } else {
+                    serde::Deserialize::deserialize(deserializer)
+                }
+            }
+        }
+    };
+}
+
+float_bits!(f32_bits, f32, u32);
+float_bits!(f64_bits, f64, u64);
+
+#[derive(Serialize, Deserialize)]
+struct TensorInfo {
+    ty: GGmlType,
+    shape: Vec<u64>,
+    offset: u64,
+}
+
+impl Serialize for GGufTensorInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TensorInfo {
+            ty: self.ty(),
+            shape: self.shape().to_vec(),
+            offset: self.offset() as _,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GGufTensorInfo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let TensorInfo { ty, shape, offset } = TensorInfo::deserialize(deserializer)?;
+        Ok(Self::new(ty, &shape, offset))
+    }
+}
+
+/// Serialized as the decoded [GGufMetaValue], so that the type of every value is kept.
+impl Serialize for GGufMetaBuf<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GGufReader::new(&self.value)
+            .read_meta_value(self.ty)
+            .map_err(|e| ser::Error::custom(format!("{e:?}")))?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GGufMetaBuf<'_> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        GGufMetaValue::deserialize(deserializer)?
+            .to_meta_buf()
+            .map_err(de::Error::custom)
+    }
+}
+
+#[test]
+fn test_serde() {
+    use crate::{
+        GGmlTokenType, GGufDocument, GGufFileHeader, GGufMetaDataValueType as Ty, GGufStream,
+        GGufTensorBuf,
+    };
+    use indexmap::IndexMap;
+    use std::io::Cursor;
+
+    let mut doc = GGufDocument::new();
+    doc.insert_meta_kv("general.architecture", GGufMetaBuf::string("llama"));
+    doc.insert_meta_kv(
+        "nested",
+        GGufMetaValue::Array(
+            Ty::Array,
+            vec![
+                GGufMetaValue::Array(Ty::F32, vec![GGufMetaValue::F32(0.1)]),
+                GGufMetaValue::Array(Ty::U64, vec![GGufMetaValue::U64(u64::MAX)]),
+                GGufMetaValue::Array(
+                    Ty::F64,
+                    vec![
+                        GGufMetaValue::F64(f64::NAN),
+                        GGufMetaValue::F64(f64::NEG_INFINITY),
+                    ],
+                ),
+            ],
+        )
+        .to_meta_buf()
+        .unwrap(),
+    );
+    doc.insert_tensor(
+        "x",
+        GGufTensorBuf {
+            ty: GGmlType::Q8_0,
+            shape: vec![32, 2],
+            data: vec![0; 68].into(),
+        },
+    );
+    let mut file = Vec::new();
+    doc.write(&mut file).unwrap();
+    let stream = GGufStream::new(Cursor::new(&file)).unwrap();
+
+    let json = serde_json::to_string(&(&stream.header, &stream.meta_kvs, &stream.tensors)).unwrap();
+    let (header, meta_kvs, tensors): (
+        GGufFileHeader,
+        IndexMap<String, GGufMetaBuf>,
+        IndexMap<String, GGufTensorInfo>,
+    ) = serde_json::from_str(&json).unwrap();
+
+    assert!(header.is_magic_correct());
+    assert_eq!(header.metadata_kv_count, stream.header.metadata_kv_count);
+    assert_eq!(
+        meta_kvs.keys().collect::<Vec<_>>(),
+        stream.meta_kvs.keys().collect::<Vec<_>>()
+    );
+    for (k, v) in &meta_kvs {
+        assert_eq!(v.ty, stream.meta_kvs[k].ty);
+        assert_eq!(v.value, stream.meta_kvs[k].value);
+    }
+    assert_eq!(
+        serde_json::to_string(&GGufMetaValue::F32(f32::INFINITY)).unwrap(),
+        r#"{"F32":"0x7f800000"}"#
+    );
+    assert_eq!(tensors["x"].ty(), GGmlType::Q8_0);
+    assert_eq!(tensors["x"].shape(), [32, 2]);
+    assert_eq!(tensors["x"].offset(), 0);
+
+    assert_eq!(serde_json::to_string(&GGmlType::Q4_0).unwrap(), r#""Q4_0""#);
+    assert_eq!(
+        serde_json::from_str::<GGufFileType>(r#""MostlyQ8_0""#).unwrap(),
+        GGufFileType::MostlyQ8_0
+    );
+    assert_eq!(
+        serde_json::from_str::<GGmlTokenType>(r#""Control""#).unwrap(),
+        GGmlTokenType::Control
+    );
+    assert!(serde_json::from_str::<GGmlType>(r#""Q9""#).is_err());
+}
@@ -0,0 +1,448 @@
+use crate::{
+    pad, GGmlType, GGufError, GGufFileHeader, GGufMetaBuf, GGufMetaDataValueType as Ty,
+    GGufMetaMap, GGufReadError, GGufTensorInfo, GGufWriter, DEFAULT_ALIGNMENT, GENERAL_ALIGNMENT,
+};
+use indexmap::IndexMap;
+use std::{
+    future::Future,
+    io::{self, Read, Seek, SeekFrom, Write},
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+/// Header, metadata and tensor infos of a GGUF file parsed from a [Read] source.
+///
+/// Metadata values are decoded into native byte order and the format of the latest GGUF version.
+/// Tensor data is not read during parsing, it can be fetched on demand if the source is also [Seek].
+pub struct GGufStream<R> {
+    reader: R,
+    /// 已从数据源读取的字节数
+    pos: u64,
+    /// The header in native byte order.
+    pub header: GGufFileHeader,
+    /// Whether the file is in native byte order.
+    pub native_endian: bool,
+    pub alignment: usize,
+    pub meta_kvs: IndexMap<String, GGufMetaBuf<'static>>,
+    pub tensors: IndexMap<String, GGufTensorInfo>,
+    /// Offset of tensor data from the start of the file, tensor offsets are relative to it.
+    pub data_offset: u64,
+}
+
+impl<R> GGufMetaMap for GGufStream<R> {
+    fn get(&self, key: &str) -> Option<(Ty, &[u8])> {
+        self.meta_kvs.get(key).map(|v| (v.ty, &*v.value))
+    }
+}
+
+impl<R: Read> GGufStream<R> {
+    /// Parses the header, metadata and tensor infos from the current position of `reader`,
+    /// leaving it at the start of tensor data.
+    pub fn new(reader: R) -> Result<Self, GGufError> {
+        let mut source = SyncSource(reader);
+        // 同步数据源不会挂起，轮询一次即可完成
+        let parsed = match pin!(parse(&mut source)).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(res) => res?,
+            Poll::Pending => unreachable!(),
+        };
+        let Parsed {
+            pos,
+            header,
+            native_endian,
+            alignment,
+            meta_kvs,
+            tensors,
+        } = parsed;
+        Ok(Self {
+            reader: source.0,
+            pos,
+            header,
+            native_endian,
+            alignment,
+            meta_kvs,
+            tensors,
+            data_offset: pos,
+        })
+    }
+
+    /// Returns the source, which is left at the start of tensor data if no tensor has been fetched.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read + Seek> GGufStream<R> {
+    /// Reads the data of a tensor and converts it into native byte order.
+    pub fn read_tensor_data(&mut self, name: &str) -> io::Result<Vec<u8>> {
+        let Some(info) = self.tensors.get(name) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("tensor {name} not found"),
+            ));
+        };
+        let ty = info.ty();
+        let pos = self.data_offset + info.offset() as u64;
+        let mut data = vec![0; info.nbytes()];
+
+        // 只知道相对位置，所以从当前位置出发移动
+        self.reader
+            .seek(SeekFrom::Current(pos as i64 - self.pos as i64))?;
+        self.pos = pos;
+        self.reader.read_exact(&mut data)?;
+        self.pos += data.len() as u64;
+
+        if !self.native_endian {
+            ty.swap_bytes(&mut data)?
+        }
+        Ok(data)
+    }
+}
+
+/// 解析使用的字节源，同步和异步的数据源各自实现，解析过程只有 [parse] 一份
+pub(crate) trait Source {
+    /// 读满 `buf`
+    async fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()>;
+    /// 读取至多 `len` 字节追加到 `buf`，返回读到的字节数
+    async fn read_at_most(&mut self, len: u64, buf: &mut Vec<u8>) -> io::Result<u64>;
+    /// 跳过至多 `len` 字节，返回跳过的字节数
+    async fn skip(&mut self, len: u64) -> io::Result<u64>;
+}
+
+struct SyncSource<R>(R);
+
+impl<R: Read> Source for SyncSource<R> {
+    async fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.0.read_exact(buf)
+    }
+
+    async fn read_at_most(&mut self, len: u64, buf: &mut Vec<u8>) -> io::Result<u64> {
+        (&mut self.0).take(len).read_to_end(buf).map(|n| n as _)
+    }
+
+    async fn skip(&mut self, len: u64) -> io::Result<u64> {
+        io::copy(&mut (&mut self.0).take(len), &mut io::sink())
+    }
+}
+
+/// 从字节源解析出的头、元信息和张量信息
+pub(crate) struct Parsed {
+    /// 已从数据源读取的字节数，即张量数据的起始位置
+    pub pos: u64,
+    pub header: GGufFileHeader,
+    pub native_endian: bool,
+    pub alignment: usize,
+    pub meta_kvs: IndexMap<String, GGufMetaBuf<'static>>,
+    pub tensors: IndexMap<String, GGufTensorInfo>,
+}
+
+/// 从 `source` 的当前位置解析头、元信息和张量信息，停在张量数据的起始位置
+pub(crate) async fn parse(source: &mut impl Source) -> Result<Parsed, GGufError> {
+    use GGufError::*;
+
+    let mut stream = StreamReader {
+        source,
+        pos: 0,
+        native_endian: true,
+        version: 3,
+    };
+
+    let header = stream.read_header().await?;
+    if !(1..=3).contains(&header.version) {
+        return Err(VersionNotSupport);
+    }
+
+    let mut alignment = DEFAULT_ALIGNMENT;
+    let mut meta_kvs = IndexMap::new();
+    for _ in 0..header.metadata_kv_count {
+        let k = stream.read_str().await?;
+        let ty = stream.read_meta_ty().await?;
+        let mut value = Vec::new();
+        stream
+            .read_value(&mut GGufWriter::new(&mut value), ty, 1)
+            .await?;
+        if k == GENERAL_ALIGNMENT {
+            let val = match ty {
+                Ty::U32 => u32::from_ne_bytes(value[..].try_into().unwrap()) as u64,
+                Ty::U64 => u64::from_ne_bytes(value[..].try_into().unwrap()),
+                ty => return Err(AlignmentTypeMismatch(ty)),
+            };
+            alignment = match usize::try_from(val) {
+                Ok(a) if a.is_power_of_two() => a,
+                _ => return Err(InvalidAlignment(val)),
+            }
+        }
+        let value = GGufMetaBuf {
+            ty,
+            value: value.into(),
+        };
+        if meta_kvs.contains_key(&k) {
+            return Err(DuplicateMetaKey(k));
+        }
+        meta_kvs.insert(k, value);
+    }
+
+    let mut tensors = IndexMap::new();
+    for _ in 0..header.tensor_count {
+        let name = stream.read_str().await?;
+        let ndim = stream.read::<u32>().await?;
+        let mut shape = Vec::new();
+        for _ in 0..ndim {
+            shape.push(stream.read_size().await?)
+        }
+        let ty = stream.read::<u32>().await?;
+        let ty = GGmlType::try_from(ty).map_err(|_| InvalidTensorType(ty))?;
+        let offset = stream.read::<u64>().await?;
+        if tensors.contains_key(&name) {
+            return Err(DuplicateTensorName(name));
+        }
+        if ty.size().checked_elements_to_bytes(&shape).is_none() {
+            return Err(IndivisibleShape(name));
+        }
+        tensors.insert(name, GGufTensorInfo::new(ty, &shape, offset));
+    }
+
+    if !tensors.is_empty() {
+        let padding = pad(stream.pos as _, alignment);
+        stream.skip(padding as _).await?;
+    }
+
+    Ok(Parsed {
+        pos: stream.pos,
+        header,
+        native_endian: stream.native_endian,
+        alignment,
+        meta_kvs,
+        tensors,
+    })
+}
+
+struct StreamReader<'a, S> {
+    source: &'a mut S,
+    pos: u64,
+    native_endian: bool,
+    version: u32,
+}
+
+impl<S: Source> StreamReader<'_, S> {
+    async fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), GGufError> {
+        self.source
+            .read_exact(buf)
+            .await
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::UnexpectedEof => GGufError::Reading(GGufReadError::Eos),
+                _ => GGufError::Io(e),
+            })?;
+        self.pos += buf.len() as u64;
+        Ok(())
+    }
+
+    async fn skip(&mut self, len: u64) -> Result<(), GGufError> {
+        let n = self.source.skip(len).await.map_err(GGufError::Io)?;
+        self.pos += n;
+        if n == len {
+            Ok(())
+        } else {
+            Err(GGufError::Reading(GGufReadError::Eos))
+        }
+    }
+
+    /// 读取不超过 8 字节的标量
+    async fn read<T: Copy>(&mut self) -> Result<T, GGufError> {
+        let mut buf = [0u8; 8];
+        let bytes = &mut buf[..size_of::<T>()];
+        self.read_exact(bytes).await?;
+        if !self.native_endian {
+            bytes.reverse()
+        }
+        Ok(unsafe { bytes.as_ptr().cast::<T>().read_unaligned() })
+    }
+
+    async fn read_meta_ty(&mut self) -> Result<Ty, GGufError> {
+        let ty = self.read::<u32>().await?;
+        Ty::try_from(ty).map_err(|_| GGufError::InvalidMetaType(ty))
+    }
+
+    async fn read_header(&mut self) -> Result<GGufFileHeader, GGufError> {
+        let magic = self.read::<[u8; 4]>().await?;
+        let version = self.read::<u32>().await?;
+
+        let mut header = GGufFileHeader::new(version, 0, 0);
+        if !header.is_native_endian() {
+            self.native_endian = false;
+            header.version = version.swap_bytes()
+        }
+        self.version = header.version;
+        if magic != *b"GGUF" {
+            return Err(GGufError::MagicMismatch);
+        }
+
+        header.tensor_count = self.read_size().await?;
+        header.metadata_kv_count = self.read_size().await?;
+        Ok(header)
+    }
+
+    async fn read_size(&mut self) -> Result<u64, GGufError> {
+        if self.version == 1 {
+            self.read::<u32>().await.map(u64::from)
+        } else {
+            self.read::<u64>().await
+        }
+    }
+
+    async fn read_str(&mut self) -> Result<String, GGufError> {
+        let len = self.read_size().await?;
+        let mut buf = Vec::new();
+        let n = self
+            .source
+            .read_at_most(len, &mut buf)
+            .await
+            .map_err(GGufError::Io)?;
+        self.pos += n;
+        if n != len {
+            return Err(GGufError::Reading(GGufReadError::Eos));
+        }
+        String::from_utf8(buf).map_err(|e| GGufError::Reading(GGufReadError::Utf8(e.utf8_error())))
+    }
+
+    /// 读取元信息值，以最新版本的格式和本机字节序写入 `writer`
+    async fn read_value(
+        &mut self,
+        writer: &mut GGufWriter<impl Write>,
+        ty: Ty,
+        len: usize,
+    ) -> Result<(), GGufError> {
+        macro_rules! copy {
+            ($ty:ty) => {
+                for _ in 0..len {
+                    writer.write(&[self.read::<$ty>().await?]).unwrap()
+                }
+            };
+        }
+
+        match ty {
+            Ty::U8 | Ty::I8 => copy!(u8),
+            Ty::U16 | Ty::I16 => copy!(u16),
+            Ty::U32 | Ty::I32 | Ty::F32 => copy!(u32),
+            Ty::U64 | Ty::I64 | Ty::F64 => copy!(u64),
+            Ty::Bool => {
+                for _ in 0..len {
+                    match self.read::<u8>().await? {
+                        b @ (0 | 1) => writer.write(&[b]).unwrap(),
+                        e => return Err(GGufError::Reading(GGufReadError::Bool(e))),
+                    }
+                }
+            }
+            Ty::String => {
+                for _ in 0..len {
+                    writer.write_str(self.read_str().await?).unwrap()
+                }
+            }
+            Ty::Array => {
+                for _ in 0..len {
+                    let ty = self.read_meta_ty().await?;
+                    let len = self.read_size().await?;
+                    writer.write(&[ty]).unwrap();
+                    writer.write(&[len]).unwrap();
+                    // 嵌套数组需要递归
+                    Box::pin(self.read_value(writer, ty, len as _)).await?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_stream() {
+    use crate::{DataFuture, GGuf, GGufDocument, GGufMetaMapExt, GGufTensorBuf};
+    use std::io::Cursor;
+
+    let mut doc = GGufDocument::new();
+    doc.alignment = 64;
+    doc.insert_meta_kv("general.architecture", GGufMetaBuf::string("llama"));
+    for (name, shape) in [("a", vec![4, 2]), ("b", vec![3])] {
+        let n = shape.iter().product::<u64>() as usize;
+        let data = (0..n)
+            .flat_map(|x| (x as f32).to_ne_bytes())
+            .collect::<Vec<_>>();
+        doc.insert_tensor(
+            name,
+            GGufTensorBuf {
+                ty: GGmlType::F32,
+                shape,
+                data: data.into(),
+            },
+        );
+    }
+
+    for native_endian in [true, false] {
+        let mut file = Vec::new();
+        doc.write_with_endian(&mut file, native_endian).unwrap();
+
+        let mut stream = GGufStream::new(Cursor::new(&file)).unwrap();
+        assert_eq!(stream.native_endian, native_endian);
+        assert_eq!(stream.alignment, 64);
+        assert_eq!(stream.general_architecture().unwrap(), "llama");
+        assert_eq!(stream.tensors["a"].shape(), [4, 2]);
+
+        let gguf = GGuf::new(&file).unwrap();
+        assert_eq!(stream.data_offset as usize, file.len() - gguf.data.len());
+        // 乱序读取张量数据
+        for name in ["b", "a", "b"] {
+            assert_eq!(
+                stream.read_tensor_data(name).unwrap(),
+                doc.tensors[name].data.get()
+            );
+        }
+        assert!(stream.read_tensor_data("c").is_err());
+    }
+
+    // 形状不能被块大小整除的张量
+    let mut file = Vec::new();
+    let mut w = GGufWriter::new(&mut file);
+    w.write_header(GGufFileHeader::new(3, 1, 0)).unwrap();
+    w.write_tensor_info("a", &[33], GGmlType::Q8_0, 0).unwrap();
+    drop(w);
+    assert!(matches!(
+        GGufStream::new(&*file),
+        Err(GGufError::IndivisibleShape(name)) if name == "a"
+    ));
+
+    // 越界的类型标签和非法的对齐
+    let mut file = Vec::new();
+    let mut w = GGufWriter::new(&mut file);
+    w.write_header(GGufFileHeader::new(3, 0, 1)).unwrap();
+    w.write_str("x").unwrap();
+    w.write(&[99u32]).unwrap();
+    drop(w);
+    assert!(matches!(
+        GGufStream::new(&*file),
+        Err(GGufError::InvalidMetaType(99))
+    ));
+
+    let mut file = Vec::new();
+    let mut w = GGufWriter::new(&mut file);
+    w.write_header(GGufFileHeader::new(3, 1, 0)).unwrap();
+    w.write_str("a").unwrap();
+    w.write(&[1u32]).unwrap();
+    w.write(&[32u64]).unwrap();
+    w.write(&[99u32]).unwrap();
+    drop(w);
+    assert!(matches!(
+        GGufStream::new(&*file),
+        Err(GGufError::InvalidTensorType(99))
+    ));
+
+    let mut file = Vec::new();
+    let mut w = GGufWriter::new(&mut file);
+    w.write_header(GGufFileHeader::new(3, 1, 1)).unwrap();
+    w.write_meta_kv(GENERAL_ALIGNMENT, Ty::U32, &0u32.to_ne_bytes())
+        .unwrap();
+    w.write_tensor_info("a", &[4], GGmlType::F32, 0).unwrap();
+    drop(w);
+    assert!(matches!(
+        GGufStream::new(&*file),
+        Err(GGufError::InvalidAlignment(0))
+    ));
+}
@@ -1,11 +1,12 @@
 ﻿use crate::{GGufReadError, GGufReader};
 use std::{
     alloc::{alloc, dealloc, Layout},
-    ptr::{copy_nonoverlapping, NonNull},
-    slice::from_raw_parts,
+    io,
+    ptr::NonNull,
+    slice::{from_raw_parts, from_raw_parts_mut},
 };
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(num_enum::TryFromPrimitive, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 #[repr(u32)]
 pub enum GGmlType {
     F32 = 0,
@@ -69,6 +70,16 @@ impl GGmlTypeSize {
         }
     }
 
+    /// Like [GGmlTypeSize::elements_to_bytes], but returns `None` instead of panicking
+    /// if the first dimension is not divisible by the block size.
+    pub fn checked_elements_to_bytes(&self, shape: &[u64]) -> Option<usize> {
+        match shape {
+            [] if self.block_size != 1 => None,
+            [last, ..] if last % self.block_size as u64 != 0 => None,
+            _ => Some(self.elements_to_bytes(shape)),
+        }
+    }
+
     #[inline]
     pub fn elements_to_bytes(&self, shape: &[u64]) -> usize {
         let blk = self.block_size as u64;
@@ -87,6 +98,48 @@ impl GGmlTypeSize {
 }
 
 impl GGmlType {
+    /// Name of the type in ggml, like `f16`, `q4_K` or `iq2_xxs`.
+    #[rustfmt::skip]
+    #[allow(deprecated)]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::F32      => "f32"     ,
+            Self::F16      => "f16"     ,
+            Self::Q4_0     => "q4_0"    ,
+            Self::Q4_1     => "q4_1"    ,
+            Self::Q4_2     => "q4_2"    ,
+            Self::Q4_3     => "q4_3"    ,
+            Self::Q5_0     => "q5_0"    ,
+            Self::Q5_1     => "q5_1"    ,
+            Self::Q8_0     => "q8_0"    ,
+            Self::Q8_1     => "q8_1"    ,
+            Self::Q2K      => "q2_K"    ,
+            Self::Q3K      => "q3_K"    ,
+            Self::Q4K      => "q4_K"    ,
+            Self::Q5K      => "q5_K"    ,
+            Self::Q6K      => "q6_K"    ,
+            Self::Q8K      => "q8_K"    ,
+            Self::IQ2XXS   => "iq2_xxs" ,
+            Self::IQ2XS    => "iq2_xs"  ,
+            Self::IQ3XXS   => "iq3_xxs" ,
+            Self::IQ1S     => "iq1_s"   ,
+            Self::IQ4NL    => "iq4_nl"  ,
+            Self::IQ3S     => "iq3_s"   ,
+            Self::IQ2S     => "iq2_s"   ,
+            Self::IQ4XS    => "iq4_xs"  ,
+            Self::I8       => "i8"      ,
+            Self::I16      => "i16"     ,
+            Self::I32      => "i32"     ,
+            Self::I64      => "i64"     ,
+            Self::F64      => "f64"     ,
+            Self::IQ1M     => "iq1_m"   ,
+            Self::BF16     => "bf16"    ,
+            Self::Q4_0_4_4 => "q4_0_4x4",
+            Self::Q4_0_4_8 => "q4_0_4x8",
+            Self::Q4_0_8_8 => "q4_0_8x8",
+        }
+    }
+
     #[rustfmt::skip]
     pub const fn size(self) -> GGmlTypeSize {
         macro_rules! size {
@@ -132,6 +185,87 @@ impl GGmlType {
         }
     }
 
+    /// Byte-swaps data of this type in place, element by element or block by block.
+    ///
+    /// Fails with [io::ErrorKind::Unsupported] if the block layout of the type is unknown,
+    /// such as the interleaved `Q4_0_4_4` family, or with [io::ErrorKind::InvalidInput]
+    /// if the length of `data` is not a multiple of the block size. `data` is not changed on failure.
+    pub fn swap_bytes(self, data: &mut [u8]) -> io::Result<()> {
+        let Some(fields) = self.swap_fields() else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("byte swapping {self:?} is not supported"),
+            ));
+        };
+        let size = self.size().type_size as usize;
+        if !data.len().is_multiple_of(size) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} bytes is not a multiple of {self:?} blocks", data.len()),
+            ));
+        }
+
+        for mut block in data.chunks_exact_mut(size) {
+            for &(width, n) in fields {
+                let (head, tail) = block.split_at_mut(width * n);
+                if width > 1 {
+                    head.chunks_exact_mut(width).for_each(<[u8]>::reverse)
+                }
+                block = tail
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether [GGmlType::swap_bytes] supports this type.
+    #[inline]
+    pub fn can_swap_bytes(self) -> bool {
+        self.swap_fields().is_some()
+    }
+
+    /// 数据块中各字段的 (宽度, 数量)，与 ggml_quants 中的结构体定义一致
+    fn swap_fields(self) -> Option<&'static [(usize, usize)]> {
+        #[rustfmt::skip]
+        let fields: &[(usize, usize)] = match self {
+            Self::F32      => &[(4, 1)],
+            Self::F16      => &[(2, 1)],
+            Self::Q4_0     => &[(2, 1), (1,  16)],
+            Self::Q4_1     => &[(2, 2), (1,  16)],
+            Self::Q5_0     => &[(2, 1), (1,  20)],
+            Self::Q5_1     => &[(2, 2), (1,  20)],
+            Self::Q8_0     => &[(2, 1), (1,  32)],
+            Self::Q8_1     => &[(2, 2), (1,  32)],
+            Self::Q2K      => &[(1, 80), (2, 2)],
+            Self::Q3K      => &[(1, 108), (2, 1)],
+            Self::Q4K      => &[(2, 2), (1, 140)],
+            Self::Q5K      => &[(2, 2), (1, 172)],
+            Self::Q6K      => &[(1, 208), (2, 1)],
+            Self::Q8K      => &[(4, 1), (1, 256), (2, 16)],
+            Self::IQ2XXS   => &[(2, 1), (2,  32)],
+            Self::IQ2XS    => &[(2, 1), (2,  32), (1, 8)],
+            Self::IQ3XXS   => &[(2, 1), (1,  96)],
+            Self::IQ1S     => &[(2, 1), (1,  32), (2, 8)],
+            Self::IQ4NL    => &[(2, 1), (1,  16)],
+            Self::IQ3S     => &[(2, 1), (1, 108)],
+            Self::IQ2S     => &[(2, 1), (1,  80)],
+            Self::IQ4XS    => &[(2, 2), (1, 132)],
+            Self::I8       => &[(1, 1)],
+            Self::I16      => &[(2, 1)],
+            Self::I32      => &[(4, 1)],
+            Self::I64      => &[(8, 1)],
+            Self::F64      => &[(8, 1)],
+            Self::IQ1M     => &[(1, 48), (2, 4)],
+            Self::BF16     => &[(2, 1)],
+            // 交错排列的块和已废弃的类型没有确定的布局
+            _              => return None,
+        };
+        debug_assert_eq!(
+            fields.iter().map(|(w, n)| w * n).sum::<usize>(),
+            self.size().type_size as usize
+        );
+        Some(fields)
+    }
+
     #[cfg(feature = "types")]
     pub const fn to_digit_layout(self) -> ggml_quants::digit_layout::DigitLayout {
         use ggml_quants::{digit_layout::types as primitive, types as quantized};
@@ -172,8 +306,9 @@ impl GGmlType {
     }
 }
 
+#[derive(Clone)]
 #[repr(transparent)]
-pub struct GGufTensorMeta<'a>(&'a [u8]);
+pub struct GGufTensorMeta<'a>(GGufReader<'a>);
 
 impl<'a> GGufReader<'a> {
     pub fn read_tensor_meta(&mut self) -> Result<GGufTensorMeta<'a>, GGufReadError> {
@@ -181,12 +316,13 @@ impl<'a> GGufReader<'a> {
 
         let _ = self.read_str()?;
         let ndim: u32 = self.read()?;
-        self.skip::<u64>(ndim as _)?
-            .skip::<GGmlType>(1)?
-            .skip::<u64>(1)?;
+        for _ in 0..ndim {
+            self.read_size()?;
+        }
+        self.skip::<GGmlType>(1)?.skip::<u64>(1)?;
 
         let data = &data[..data.len() - self.remaining().len()];
-        Ok(unsafe { GGufTensorMeta::new_unchecked(data) })
+        Ok(unsafe { GGufTensorMeta::from_reader_unchecked(self.with_data(data)) })
     }
 }
 
@@ -198,7 +334,18 @@ impl<'a> GGufTensorMeta<'a> {
     /// The caller must ensure that the input data is valid for the [GGufTensorMeta] type.
     #[inline]
     pub const unsafe fn new_unchecked(data: &'a [u8]) -> Self {
-        Self(data)
+        Self(GGufReader::new(data))
+    }
+
+    /// Creates a new [GGufTensorMeta] instance from the remaining data of a reader, in the format of the reader,
+    /// without performing any validation on the input data.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the remaining data of the reader is valid for the [GGufTensorMeta] type.
+    #[inline]
+    pub const unsafe fn from_reader_unchecked(reader: GGufReader<'a>) -> Self {
+        Self(reader)
     }
 
     #[inline]
@@ -206,31 +353,33 @@ impl<'a> GGufTensorMeta<'a> {
         GGufReader::new(data).read_tensor_meta()
     }
 
+    #[inline]
+    pub const fn is_native_endian(&self) -> bool {
+        self.0.is_native_endian()
+    }
+
     #[inline]
     pub fn name(&self) -> &'a str {
-        let mut reader = GGufReader::new(self.0);
+        let mut reader = self.reader();
         unsafe { reader.read_str_unchecked() }
     }
 
     #[inline]
     pub fn to_info(&self) -> GGufTensorInfo {
-        let mut reader = GGufReader::new(self.0);
+        let mut reader = self.reader();
         let ndim: u32 = reader.skip_str().unwrap().read().unwrap();
-        let layout = Layout::array::<u64>(ndim as _).unwrap();
-        let shape = unsafe {
-            let dst = alloc(layout);
-            copy_nonoverlapping(reader.remaining().as_ptr(), dst, layout.size());
-            NonNull::new_unchecked(dst).cast()
-        };
-        let ty = reader.skip::<u64>(ndim as _).unwrap().read().unwrap();
+        let shape = (0..ndim)
+            .map(|_| reader.read_size().unwrap())
+            .collect::<Vec<_>>();
+        let ty = reader.read().unwrap();
         let offset = reader.read().unwrap();
 
-        GGufTensorInfo {
-            ty,
-            ndim,
-            shape,
-            offset,
-        }
+        GGufTensorInfo::new(ty, &shape, offset)
+    }
+
+    #[inline]
+    fn reader(&self) -> GGufReader<'a> {
+        self.0.clone()
     }
 }
 
@@ -242,6 +391,24 @@ pub struct GGufTensorInfo {
 }
 
 impl GGufTensorInfo {
+    pub(crate) fn new(ty: GGmlType, shape: &[u64], offset: u64) -> Self {
+        let ndim = shape.len();
+        let shape = if ndim == 0 {
+            NonNull::dangling()
+        } else {
+            let layout = Layout::array::<u64>(ndim).unwrap();
+            let ptr = unsafe { NonNull::new_unchecked(alloc(layout)).cast::<u64>() };
+            unsafe { from_raw_parts_mut(ptr.as_ptr(), ndim) }.copy_from_slice(shape);
+            ptr
+        };
+        Self {
+            ty,
+            ndim: ndim as _,
+            shape,
+            offset,
+        }
+    }
+
     #[inline]
     pub const fn ty(&self) -> GGmlType {
         self.ty
@@ -265,8 +432,79 @@ impl GGufTensorInfo {
 
 impl Drop for GGufTensorInfo {
     fn drop(&mut self) {
+        if self.ndim == 0 {
+            return;
+        }
         let ptr = self.shape.as_ptr().cast();
         let layout = Layout::array::<u64>(self.ndim as _).unwrap();
         unsafe { dealloc(ptr, layout) }
     }
 }
+
+#[test]
+fn test_swap_bytes() {
+    use GGmlType as Ty;
+    for ty in [
+        Ty::F32,
+        Ty::F16,
+        Ty::Q4_0,
+        Ty::Q4_1,
+        Ty::Q5_0,
+        Ty::Q5_1,
+        Ty::Q8_0,
+        Ty::Q8_1,
+        Ty::Q2K,
+        Ty::Q3K,
+        Ty::Q4K,
+        Ty::Q5K,
+        Ty::Q6K,
+        Ty::Q8K,
+        Ty::IQ2XXS,
+        Ty::IQ2XS,
+        Ty::IQ3XXS,
+        Ty::IQ1S,
+        Ty::IQ4NL,
+        Ty::IQ3S,
+        Ty::IQ2S,
+        Ty::IQ4XS,
+        Ty::I8,
+        Ty::I16,
+        Ty::I32,
+        Ty::I64,
+        Ty::F64,
+        Ty::IQ1M,
+        Ty::BF16,
+    ] {
+        let size = ty.size().type_size as usize;
+        let data = (0..size * 2).map(|i| i as u8).collect::<Vec<_>>();
+        let mut swapped = data.clone();
+        ty.swap_bytes(&mut swapped).unwrap();
+        ty.swap_bytes(&mut swapped).unwrap();
+        assert_eq!(data, swapped, "{ty:?}");
+    }
+
+    // 与 ggml-common.h 中的块大小一致
+    assert_eq!(Ty::Q8K.size().type_size, 292);
+    assert_eq!(Ty::IQ3XXS.size().type_size, 98);
+
+    let mut data = [1.0f32.to_be_bytes(), 2.0f32.to_be_bytes()].concat();
+    GGmlType::F32.swap_bytes(&mut data).unwrap();
+    assert_eq!(data, [1.0f32.to_le_bytes(), 2.0f32.to_le_bytes()].concat());
+
+    // IQ1M 的 scales 按 u16 读取
+    let mut data = (0..56).collect::<Vec<u8>>();
+    Ty::IQ1M.swap_bytes(&mut data).unwrap();
+    assert_eq!(data[..48], (0..48).collect::<Vec<_>>());
+    assert_eq!(data[48..], [49, 48, 51, 50, 53, 52, 55, 54]);
+
+    let mut data = vec![0; 18];
+    assert_eq!(
+        Ty::Q4_0_4_4.swap_bytes(&mut data).unwrap_err().kind(),
+        std::io::ErrorKind::Unsupported
+    );
+    assert!(!Ty::Q4_0_4_4.can_swap_bytes());
+    assert_eq!(
+        Ty::F32.swap_bytes(&mut data[..6]).unwrap_err().kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+}
@@ -0,0 +1,319 @@
+mod shape;
+
+use std::{error::Error, fmt, str::FromStr};
+
+/// Name of a tensor in the llama.cpp scheme, such as `token_embd.weight` or `blk.12.ffn_down_exps.weight`.
+///
+/// Names are ordered by block, kind and suffix, global tensors come first.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct GGufTensorName {
+    /// Index of the block, `None` for global tensors.
+    pub block: Option<usize>,
+    pub kind: GGufTensorKind,
+    /// `None` for tensors without a suffix, such as `ssm_a` of mamba.
+    pub suffix: Option<GGufTensorSuffix>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum GGufTensorSuffix {
+    Weight,
+    Bias,
+    Scale,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GGufTensorNameError(pub String);
+
+impl GGufTensorName {
+    /// Name of a tensor in block `block`, or a global tensor if `block` is `None`.
+    ///
+    /// Panics if `kind` is a block tensor but `block` is `None`, or the other way around.
+    pub fn new(
+        block: Option<usize>,
+        kind: GGufTensorKind,
+        suffix: Option<GGufTensorSuffix>,
+    ) -> Self {
+        assert_eq!(block.is_some(), kind.is_block());
+        Self {
+            block,
+            kind,
+            suffix,
+        }
+    }
+
+    /// Name of the weight of `kind` in block `block`.
+    #[inline]
+    pub fn blk_weight(block: usize, kind: GGufTensorKind) -> Self {
+        Self::new(Some(block), kind, Some(GGufTensorSuffix::Weight))
+    }
+}
+
+impl GGufTensorSuffix {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Weight => "weight",
+            Self::Bias => "bias",
+            Self::Scale => "scale",
+        }
+    }
+}
+
+impl FromStr for GGufTensorName {
+    type Err = GGufTensorNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || GGufTensorNameError(s.into());
+
+        let (head, suffix) = match s.rsplit_once('.') {
+            Some((head, "weight")) => (head, Some(GGufTensorSuffix::Weight)),
+            Some((head, "bias")) => (head, Some(GGufTensorSuffix::Bias)),
+            Some((head, "scale")) => (head, Some(GGufTensorSuffix::Scale)),
+            _ => (s, None),
+        };
+        let (block, kind) = match head.strip_prefix("blk.") {
+            Some(body) => {
+                let (i, kind) = body.split_once('.').ok_or_else(err)?;
+                if i.is_empty() || !i.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(err());
+                }
+                (Some(i.parse().map_err(|_| err())?), kind)
+            }
+            None => (None, head),
+        };
+        let kind = GGufTensorKind::from_name(kind)
+            .filter(|kind| kind.is_block() == block.is_some())
+            .ok_or_else(err)?;
+
+        Ok(Self {
+            block,
+            kind,
+            suffix,
+        })
+    }
+}
+
+impl fmt::Display for GGufTensorName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(i) = self.block {
+            write!(f, "blk.{i}.")?
+        }
+        write!(f, "{}", self.kind.name())?;
+        if let Some(suffix) = self.suffix {
+            write!(f, ".{}", suffix.name())?
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GGufTensorNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "not a standard tensor name: {}", self.0)
+    }
+}
+
+impl Error for GGufTensorNameError {}
+
+macro_rules! kinds {
+    (
+        global: { $( $(#[$gmeta:meta])* $global:ident => $gname:literal, )* }
+        block: { $( $(#[$bmeta:meta])* $block:ident => $bname:literal, )* }
+    ) => {
+        /// Kind of a tensor, see `MODEL_TENSOR` in gguf-py for the catalogue.
+        ///
+        /// Kinds are ordered the way tensors are usually laid out in a file.
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        pub enum GGufTensorKind {
+            $( $(#[$gmeta])* $global, )*
+            $( $(#[$bmeta])* $block, )*
+        }
+
+        impl GGufTensorKind {
+            /// All kinds in order.
+            pub const ALL: &'static [Self] = &[ $( Self::$global, )* $( Self::$block, )* ];
+
+            /// Name of the kind, without the block prefix and the suffix.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $( Self::$global => $gname, )*
+                    $( Self::$block => $bname, )*
+                }
+            }
+
+            /// Whether tensors of the kind are in blocks.
+            pub fn is_block(&self) -> bool {
+                match self {
+                    $( Self::$global => false, )*
+                    $( Self::$block => true, )*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $( $gname => Some(Self::$global), )*
+                    $( $bname => Some(Self::$block), )*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+kinds! {
+    global: {
+        TokenEmbd => "token_embd",
+        OutputNorm => "output_norm",
+        Output => "output",
+        TokenEmbdNorm => "token_embd_norm",
+        TokenTypes => "token_types",
+        PosEmbd => "position_embd",
+        RopeFreqs => "rope_freqs",
+        RopeFactorsLong => "rope_factors_long",
+        RopeFactorsShort => "rope_factors_short",
+        Cls => "cls",
+        ClsOutput => "cls.output",
+    }
+    block: {
+        AttnNorm => "attn_norm",
+        AttnNorm2 => "attn_norm_2",
+        AttnQkv => "attn_qkv",
+        AttnQ => "attn_q",
+        AttnK => "attn_k",
+        AttnV => "attn_v",
+        AttnOutput => "attn_output",
+        FfnNorm => "ffn_norm",
+        /// `ffn_gate` and `ffn_up` concatenated, as written by `gguf-utils` when linear layers are merged.
+        FfnGateUp => "ffn_gate_up",
+        FfnUp => "ffn_up",
+        FfnGate => "ffn_gate",
+        FfnDown => "ffn_down",
+        /// Legacy name of [GGufTensorKind::FfnUpExps] found in older files.
+        FfnUpExp => "ffn_up_exp",
+        FfnUpExps => "ffn_up_exps",
+        FfnGateInp => "ffn_gate_inp",
+        /// Legacy name of [GGufTensorKind::FfnGateExps] found in older files.
+        FfnGateExp => "ffn_gate_exp",
+        FfnGateExps => "ffn_gate_exps",
+        /// Legacy name of [GGufTensorKind::FfnDownExps] found in older files.
+        FfnDownExp => "ffn_down_exp",
+        FfnDownExps => "ffn_down_exps",
+        AttnQNorm => "attn_q_norm",
+        AttnKNorm => "attn_k_norm",
+        AttnOutputNorm => "attn_output_norm",
+        AttnPostNorm => "post_attention_norm",
+        AttnRotEmbd => "attn_rot_embd",
+        AttnSubNorm => "attn_sub_norm",
+        AttnQA => "attn_q_a",
+        AttnQB => "attn_q_b",
+        AttnKvAMqa => "attn_kv_a_mqa",
+        AttnKvB => "attn_kv_b",
+        AttnQANorm => "attn_q_a_norm",
+        AttnKvANorm => "attn_kv_a_norm",
+        FfnPostNorm => "post_ffw_norm",
+        FfnSubNorm => "ffn_sub_norm",
+        FfnAct => "ffn",
+        FfnNormExps => "ffn_norm_exps",
+        FfnGateInpShexp => "ffn_gate_inp_shexp",
+        FfnGateShexp => "ffn_gate_shexp",
+        FfnUpShexp => "ffn_up_shexp",
+        FfnDownShexp => "ffn_down_shexp",
+        FfnExpProbsB => "exp_probs_b",
+        LayerOutputNorm => "layer_output_norm",
+        SsmIn => "ssm_in",
+        SsmConv1d => "ssm_conv1d",
+        SsmX => "ssm_x",
+        SsmDt => "ssm_dt",
+        SsmA => "ssm_a",
+        SsmD => "ssm_d",
+        SsmOut => "ssm_out",
+        TimeMixW1 => "time_mix_w1",
+        TimeMixW2 => "time_mix_w2",
+        TimeMixLerpX => "time_mix_lerp_x",
+        TimeMixLerpK => "time_mix_lerp_k",
+        TimeMixLerpV => "time_mix_lerp_v",
+        TimeMixLerpR => "time_mix_lerp_r",
+        TimeMixLerpG => "time_mix_lerp_g",
+        TimeMixLerpW => "time_mix_lerp_w",
+        TimeMixFirst => "time_mix_first",
+        TimeMixDecay => "time_mix_decay",
+        TimeMixDecayW1 => "time_mix_decay_w1",
+        TimeMixDecayW2 => "time_mix_decay_w2",
+        TimeMixKey => "time_mix_key",
+        TimeMixValue => "time_mix_value",
+        TimeMixReceptance => "time_mix_receptance",
+        TimeMixGate => "time_mix_gate",
+        TimeMixLn => "time_mix_ln",
+        TimeMixOutput => "time_mix_output",
+        ChannelMixLerpK => "channel_mix_lerp_k",
+        ChannelMixLerpR => "channel_mix_lerp_r",
+        ChannelMixKey => "channel_mix_key",
+        ChannelMixReceptance => "channel_mix_receptance",
+        ChannelMixValue => "channel_mix_value",
+    }
+}
+
+impl GGufTensorKind {
+    /// Whether the kind is a normalization, whose name ends with `_norm`.
+    #[inline]
+    pub fn is_norm(&self) -> bool {
+        self.name().ends_with("_norm")
+    }
+}
+
+#[test]
+fn test_tensor_name() {
+    use GGufTensorKind as K;
+
+    for name in [
+        "token_embd.weight",
+        "cls.output.bias",
+        "blk.12.ffn_down_exps.weight",
+        "blk.0.attn_q.bias",
+        "blk.3.ssm_a",
+        "blk.1.ffn_down.scale",
+    ] {
+        assert_eq!(name.parse::<GGufTensorName>().unwrap().to_string(), name)
+    }
+    assert_eq!(
+        "blk.12.ffn_down_exps.weight".parse(),
+        Ok(GGufTensorName::blk_weight(12, K::FfnDownExps))
+    );
+    for name in [
+        "blk.x.attn_q.weight",
+        "blk.+1.attn_q.weight",
+        "blk.0.output.weight",
+        "attn_q.weight",
+        "v.blk.0.attn_q.weight",
+        "blk.0.attn_q.extra",
+    ] {
+        assert!(name.parse::<GGufTensorName>().is_err(), "{name}")
+    }
+    assert!(K::ALL.iter().all(|k| K::from_name(k.name()) == Some(*k)));
+
+    let mut names = [
+        "blk.1.attn_q.weight",
+        "blk.0.ffn_down_exps.weight",
+        "blk.0.ffn_down_exp.weight",
+        "blk.0.ffn_gate_exp.weight",
+        "blk.0.ffn_down.weight",
+        "blk.0.attn_q.bias",
+        "output.weight",
+        "blk.0.attn_q.weight",
+        "token_embd.weight",
+    ]
+    .map(|s| s.parse::<GGufTensorName>().unwrap());
+    names.sort_unstable();
+    assert_eq!(
+        names.map(|n| n.to_string()),
+        [
+            "token_embd.weight",
+            "output.weight",
+            "blk.0.attn_q.weight",
+            "blk.0.attn_q.bias",
+            "blk.0.ffn_down.weight",
+            "blk.0.ffn_gate_exp.weight",
+            "blk.0.ffn_down_exp.weight",
+            "blk.0.ffn_down_exps.weight",
+            "blk.1.attn_q.weight",
+        ]
+    );
+}
@@ -0,0 +1,134 @@
+use super::{GGufTensorKind as K, GGufTensorName, GGufTensorSuffix};
+use crate::{GGufHParams, GGufTransformerHParams};
+
+impl GGufHParams {
+    /// Expected shape of the tensor `name` in ggml order, the innermost dimension first.
+    ///
+    /// `n_vocab` is the number of tokens of the vocabulary.
+    /// Returns `None` if the architecture has no such tensor, or its shape is not determined by the hyperparameters.
+    /// Biases take the shape of the weight without the innermost dimension.
+    pub fn tensor_shape(&self, name: &GGufTensorName, n_vocab: usize) -> Option<Vec<u64>> {
+        let weight = match self {
+            Self::Llama(p) => {
+                let t = &p.transformer;
+                let (d, ff, n) = (t.embedding_length, t.feed_forward_length, p.expert_count);
+                match name.kind {
+                    K::FfnGateInp if n > 0 => vec![d, n],
+                    K::FfnGateExps | K::FfnUpExps if n > 0 => vec![d, ff, n],
+                    K::FfnDownExps if n > 0 => vec![ff, d, n],
+                    K::FfnGateUp | K::FfnGate | K::FfnUp | K::FfnDown if n > 0 => return None,
+                    kind => transformer(t, kind, n_vocab)?,
+                }
+            }
+            Self::Qwen2(p) => transformer(&p.transformer, name.kind, n_vocab)?,
+            Self::Gemma(p) => transformer(&p.transformer, name.kind, n_vocab)?,
+            Self::MiniCpm(p) => transformer(&p.transformer, name.kind, n_vocab)?,
+            Self::Phi3(p) => {
+                let t = &p.transformer;
+                match name.kind {
+                    // phi3 的 ffn_up 是 gate 和 up 的拼接
+                    K::FfnUp => vec![t.embedding_length, 2 * t.feed_forward_length],
+                    K::FfnGate | K::FfnGateUp => return None,
+                    K::RopeFactorsLong | K::RopeFactorsShort => vec![t.rope.dimension_count / 2],
+                    kind => transformer(t, kind, n_vocab)?,
+                }
+            }
+            Self::Mamba(p) => {
+                let (d, di, ds, r) = (
+                    p.embedding_length,
+                    p.inner_size,
+                    p.state_size,
+                    p.time_step_rank,
+                );
+                match name.kind {
+                    K::TokenEmbd | K::Output => vec![d, n_vocab],
+                    K::OutputNorm | K::AttnNorm => vec![d],
+                    K::SsmIn => vec![d, 2 * di],
+                    K::SsmConv1d => vec![p.conv_kernel, di],
+                    K::SsmX => vec![di, r + 2 * ds],
+                    K::SsmDt => vec![r, di],
+                    K::SsmA => vec![ds, di],
+                    K::SsmD => vec![di],
+                    K::SsmOut => vec![di, d],
+                    _ => return None,
+                }
+            }
+            Self::Bert(p) => {
+                let (d, ff) = (p.embedding_length, p.feed_forward_length);
+                match name.kind {
+                    K::TokenEmbd => vec![d, n_vocab],
+                    K::PosEmbd => vec![d, p.context_length],
+                    K::TokenEmbdNorm | K::AttnOutputNorm | K::LayerOutputNorm => vec![d],
+                    K::AttnQ | K::AttnK | K::AttnV | K::AttnOutput => vec![d, d],
+                    K::AttnQkv => vec![d, 3 * d],
+                    K::FfnUp => vec![d, ff],
+                    K::FfnDown => vec![ff, d],
+                    _ => return None,
+                }
+            }
+            // clip 使用 `v.blk.N.` 前缀的另一套命名
+            Self::Clip(_) => return None,
+        };
+
+        let shape = match name.suffix {
+            Some(GGufTensorSuffix::Bias) if weight.len() > 1 => weight[1..].to_vec(),
+            Some(GGufTensorSuffix::Scale) => vec![1],
+            _ => weight,
+        };
+        Some(shape.into_iter().map(|d| d as u64).collect())
+    }
+}
+
+/// 标准 transformer 解码器的张量形状
+fn transformer(t: &GGufTransformerHParams, kind: K, n_vocab: usize) -> Option<Vec<usize>> {
+    let d = t.embedding_length;
+    let ff = t.feed_forward_length;
+    let q = t.head_count * t.key_length;
+    let k = t.head_count_kv * t.key_length;
+    let v = t.head_count_kv * t.value_length;
+    let o = t.head_count * t.value_length;
+    let shape = match kind {
+        K::TokenEmbd | K::Output => vec![d, n_vocab],
+        K::OutputNorm | K::AttnNorm | K::FfnNorm => vec![d],
+        K::RopeFreqs => vec![t.rope.dimension_count / 2],
+        K::AttnQ => vec![d, q],
+        K::AttnK => vec![d, k],
+        K::AttnV => vec![d, v],
+        K::AttnQkv => vec![d, q + k + v],
+        K::AttnOutput => vec![o, d],
+        K::AttnQNorm | K::AttnKNorm => vec![t.key_length],
+        K::FfnGate | K::FfnUp => vec![d, ff],
+        K::FfnGateUp => vec![d, 2 * ff],
+        K::FfnDown => vec![ff, d],
+        _ => return None,
+    };
+    Some(shape)
+}
+
+#[test]
+fn test_tensor_shape() {
+    use crate::{GGufDocument, GGufMetaValue as V};
+
+    let mut doc = GGufDocument::new();
+    let mut set = |k: &str, v: V| {
+        doc.insert_meta_kv(k.to_string(), v.to_meta_buf().unwrap());
+    };
+    set("general.architecture", V::String("llama".into()));
+    set("llama.context_length", V::U32(2048));
+    set("llama.embedding_length", V::U32(256));
+    set("llama.block_count", V::U32(4));
+    set("llama.feed_forward_length", V::U32(768));
+    set("llama.attention.head_count", V::U32(8));
+    set("llama.attention.head_count_kv", V::U32(2));
+    set("llama.attention.layer_norm_rms_epsilon", V::F32(1e-5));
+    let hparams = GGufHParams::from_meta(&doc).unwrap();
+
+    let shape = |name: &str| hparams.tensor_shape(&name.parse().unwrap(), 1000);
+    assert_eq!(shape("token_embd.weight"), Some(vec![256, 1000]));
+    assert_eq!(shape("blk.0.attn_k.weight"), Some(vec![256, 64]));
+    assert_eq!(shape("blk.0.attn_k.bias"), Some(vec![64]));
+    assert_eq!(shape("blk.0.attn_qkv.weight"), Some(vec![256, 384]));
+    assert_eq!(shape("blk.0.ffn_down.weight"), Some(vec![768, 256]));
+    assert_eq!(shape("blk.0.ffn_down_exps.weight"), None);
+    assert_eq!(shape("blk.0.ssm_a"), None);
+}
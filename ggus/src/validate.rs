@@ -0,0 +1,446 @@
+use crate::{
+    pad, GGmlType, GGuf, GGufMetaDataValueType as Ty, GGufReadError, GGufReader, DEFAULT_ALIGNMENT,
+    GENERAL_ALIGNMENT,
+};
+use std::{collections::HashSet, fmt};
+
+/// A problem found by [GGuf::validate], located by a byte offset from the start of the file.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GGufDiagnostic {
+    pub offset: usize,
+    pub kind: GGufDiagnosticKind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum GGufSeverity {
+    /// The file can be read, but something is unusual.
+    Warning,
+    /// The file is malformed.
+    Error,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum GGufDiagnosticKind {
+    /// Failed to read the file, the rest of the file is not checked.
+    Reading(GGufReadError),
+    MagicMismatch,
+    VersionNotSupport(u32),
+    InvalidMetaType(u32),
+    DuplicateMetaKey(String),
+    AlignmentTypeMismatch(Ty),
+    /// `general.alignment` is not a positive power of two.
+    InvalidAlignment(u64),
+    InvalidTensorType {
+        name: String,
+        ty: u32,
+    },
+    UnsupportedTensorType {
+        name: String,
+        ty: GGmlType,
+    },
+    DuplicateTensorName(String),
+    /// The first dimension is not divisible by the block size of the type.
+    IndivisibleShape {
+        name: String,
+        ty: GGmlType,
+        dim: u64,
+    },
+    /// The tensor offset is not a multiple of the alignment.
+    MisalignedTensor {
+        name: String,
+        alignment: usize,
+    },
+    TensorOutOfBounds {
+        name: String,
+    },
+    OverlappingTensors {
+        name: String,
+        other: String,
+    },
+    /// Unused bytes between tensors, more than the alignment requires.
+    Gap {
+        len: usize,
+    },
+    /// Unused bytes after the last tensor.
+    TrailingBytes {
+        len: usize,
+    },
+}
+
+impl GGufDiagnosticKind {
+    pub const fn severity(&self) -> GGufSeverity {
+        match self {
+            Self::Gap { .. } | Self::TrailingBytes { .. } => GGufSeverity::Warning,
+            _ => GGufSeverity::Error,
+        }
+    }
+}
+
+impl GGufDiagnostic {
+    #[inline]
+    pub const fn severity(&self) -> GGufSeverity {
+        self.kind.severity()
+    }
+
+    #[inline]
+    pub const fn is_error(&self) -> bool {
+        matches!(self.severity(), GGufSeverity::Error)
+    }
+}
+
+impl fmt::Display for GGufDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use GGufDiagnosticKind::*;
+        let severity = match self.severity() {
+            GGufSeverity::Warning => "warning",
+            GGufSeverity::Error => "error",
+        };
+        write!(f, "{severity} at {:#x}: ", self.offset)?;
+        match &self.kind {
+            Reading(e) => write!(f, "reading error: {e:?}"),
+            MagicMismatch => f.write_str("magic mismatch"),
+            VersionNotSupport(v) => write!(f, "version {v} not support"),
+            InvalidMetaType(ty) => write!(f, "invalid meta value type {ty}"),
+            DuplicateMetaKey(key) => write!(f, "duplicate meta key: {key}"),
+            AlignmentTypeMismatch(ty) => write!(f, "alignment type mismatch: {ty:?}"),
+            InvalidAlignment(a) => write!(f, "invalid alignment {a}"),
+            InvalidTensorType { name, ty } => write!(f, "tensor {name} has invalid type {ty}"),
+            UnsupportedTensorType { name, ty } => {
+                write!(f, "tensor {name} has unsupported type {ty:?}")
+            }
+            DuplicateTensorName(name) => write!(f, "duplicate tensor name: {name}"),
+            IndivisibleShape { name, ty, dim } => write!(
+                f,
+                "tensor {name} has {dim} elements per row, not divisible by block size {} of {ty:?}",
+                ty.size().block_size
+            ),
+            MisalignedTensor { name, alignment } => {
+                write!(f, "tensor {name} not aligned to {alignment} bytes")
+            }
+            TensorOutOfBounds { name } => write!(f, "tensor {name} out of file"),
+            OverlappingTensors { name, other } => write!(f, "tensor {name} overlaps {other}"),
+            Gap { len } => write!(f, "{len} unused bytes between tensors"),
+            TrailingBytes { len } => write!(f, "{len} unused bytes after tensors"),
+        }
+    }
+}
+
+impl GGuf<'_> {
+    /// Checks the structure of a file without panicking on malformed data.
+    ///
+    /// Besides what [GGuf::new] checks, tensors are checked for valid types, divisible shapes,
+    /// aligned offsets, overlaps and unused bytes in between.
+    pub fn validate(data: &[u8]) -> Vec<GGufDiagnostic> {
+        let mut ans = Vec::new();
+        let _ = Validator {
+            data,
+            reader: GGufReader::new(data),
+            ans: &mut ans,
+        }
+        .run();
+        ans.sort_by_key(|d| d.offset);
+        ans
+    }
+}
+
+struct Validator<'a, 'b> {
+    data: &'a [u8],
+    reader: GGufReader<'a>,
+    ans: &'b mut Vec<GGufDiagnostic>,
+}
+
+/// 遇到无法继续检查的错误
+struct Stop;
+
+struct TensorRange {
+    name: String,
+    info_offset: usize,
+    offset: usize,
+    nbytes: usize,
+}
+
+impl Validator<'_, '_> {
+    fn pos(&self) -> usize {
+        self.data.len() - self.reader.remaining().len()
+    }
+
+    fn report(&mut self, offset: usize, kind: GGufDiagnosticKind) {
+        self.ans.push(GGufDiagnostic { offset, kind })
+    }
+
+    fn check<T>(&mut self, offset: usize, res: Result<T, GGufReadError>) -> Result<T, Stop> {
+        res.map_err(|e| {
+            self.report(offset, GGufDiagnosticKind::Reading(e));
+            Stop
+        })
+    }
+
+    fn read<T: Copy>(&mut self) -> Result<T, Stop> {
+        let pos = self.pos();
+        let res = self.reader.read();
+        self.check(pos, res)
+    }
+
+    fn read_size(&mut self) -> Result<u64, Stop> {
+        let pos = self.pos();
+        let res = self.reader.read_size();
+        self.check(pos, res)
+    }
+
+    fn read_str(&mut self) -> Result<String, Stop> {
+        let pos = self.pos();
+        let res = self.reader.read_str().map(String::from);
+        self.check(pos, res)
+    }
+
+    fn read_meta_ty(&mut self) -> Result<Ty, Stop> {
+        let pos = self.pos();
+        let ty = self.read::<u32>()?;
+        Ty::try_from(ty).map_err(|_| {
+            self.report(pos, GGufDiagnosticKind::InvalidMetaType(ty));
+            Stop
+        })
+    }
+
+    fn skip_meta_value(&mut self, ty: Ty, len: u64) -> Result<(), Stop> {
+        for _ in 0..len {
+            match ty {
+                Ty::U8 | Ty::I8 => drop(self.read::<u8>()?),
+                Ty::U16 | Ty::I16 => drop(self.read::<u16>()?),
+                Ty::U32 | Ty::I32 | Ty::F32 => drop(self.read::<u32>()?),
+                Ty::U64 | Ty::I64 | Ty::F64 => drop(self.read::<u64>()?),
+                Ty::Bool => {
+                    let pos = self.pos();
+                    let res = self.reader.read_bool();
+                    self.check(pos, res)?;
+                }
+                Ty::String => drop(self.read_str()?),
+                Ty::Array => {
+                    let ty = self.read_meta_ty()?;
+                    let len = self.read_size()?;
+                    self.skip_meta_value(ty, len)?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn run(mut self) -> Result<(), Stop> {
+        use GGufDiagnosticKind::*;
+
+        // 文件头
+
+        let header = self.reader.read_header();
+        let header = self.check(0, header)?;
+        if !header.is_magic_correct() {
+            self.report(0, MagicMismatch);
+            return Err(Stop);
+        }
+        if !(1..=3).contains(&header.version) {
+            self.report(4, VersionNotSupport(header.version));
+            return Err(Stop);
+        }
+
+        // 元信息
+
+        let mut alignment = DEFAULT_ALIGNMENT;
+        let mut keys = HashSet::new();
+        for _ in 0..header.metadata_kv_count {
+            let pos = self.pos();
+            let key = self.read_str()?;
+            let ty = self.read_meta_ty()?;
+            let value = self.pos();
+            self.skip_meta_value(ty, 1)?;
+
+            if key == GENERAL_ALIGNMENT {
+                // 值已经读过一遍，不会再出错
+                let mut reader = self.reader.with_data(&self.data[value..]);
+                let val = match ty {
+                    Ty::U32 => Some(reader.read::<u32>().unwrap() as u64),
+                    Ty::U64 => Some(reader.read::<u64>().unwrap()),
+                    ty => {
+                        self.report(value, AlignmentTypeMismatch(ty));
+                        None
+                    }
+                };
+                match val {
+                    Some(val) if val.is_power_of_two() && usize::try_from(val).is_ok() => {
+                        alignment = val as _
+                    }
+                    Some(val) => self.report(value, InvalidAlignment(val)),
+                    None => {}
+                }
+            }
+            if !keys.insert(key.clone()) {
+                self.report(pos, DuplicateMetaKey(key))
+            }
+        }
+
+        // 张量信息
+
+        let mut names = HashSet::new();
+        let mut tensors = Vec::new();
+        for _ in 0..header.tensor_count {
+            let pos = self.pos();
+            let name = self.read_str()?;
+            let ndim = self.read::<u32>()?;
+            let shape = (0..ndim)
+                .map(|_| self.read_size())
+                .collect::<Result<Vec<_>, _>>()?;
+            let ty = self.read::<u32>()?;
+            let offset = self.read::<u64>()?;
+
+            if !names.insert(name.clone()) {
+                self.report(pos, DuplicateTensorName(name.clone()))
+            }
+            let Ok(ty) = GGmlType::try_from(ty) else {
+                self.report(pos, InvalidTensorType { name, ty });
+                continue;
+            };
+            #[allow(deprecated)]
+            if matches!(
+                ty,
+                GGmlType::Q4_2
+                    | GGmlType::Q4_3
+                    | GGmlType::Q4_0_4_4
+                    | GGmlType::Q4_0_4_8
+                    | GGmlType::Q4_0_8_8
+            ) {
+                self.report(pos, UnsupportedTensorType { name, ty });
+                continue;
+            }
+            let Some(nbytes) = ty.size().checked_elements_to_bytes(&shape) else {
+                let dim = shape.first().copied().unwrap_or(1);
+                self.report(pos, IndivisibleShape { name, ty, dim });
+                continue;
+            };
+            if offset % alignment as u64 != 0 {
+                let name = name.clone();
+                self.report(pos, MisalignedTensor { name, alignment })
+            }
+            tensors.push(TensorRange {
+                name,
+                info_offset: pos,
+                offset: offset as _,
+                nbytes,
+            })
+        }
+
+        // 张量数据
+
+        let base = if header.tensor_count == 0 {
+            self.pos()
+        } else {
+            self.pos() + pad(self.pos(), alignment)
+        };
+        let len = self.data.len().saturating_sub(base);
+
+        tensors.sort_by_key(|t| t.offset);
+        let mut end = 0;
+        let mut last = None::<&TensorRange>;
+        let mut truncated = false;
+        let mut reports = Vec::new();
+        for t in &tensors {
+            if t.offset.checked_add(t.nbytes).is_none_or(|end| end > len) {
+                reports.push((
+                    t.info_offset,
+                    TensorOutOfBounds {
+                        name: t.name.clone(),
+                    },
+                ));
+                truncated = true;
+                continue;
+            }
+            if let Some(last) = last.filter(|_| t.offset < end) {
+                reports.push((
+                    base + t.offset,
+                    OverlappingTensors {
+                        name: t.name.clone(),
+                        other: last.name.clone(),
+                    },
+                ))
+            } else if t.offset - end >= alignment {
+                reports.push((
+                    base + end,
+                    Gap {
+                        len: t.offset - end,
+                    },
+                ))
+            }
+            if t.offset + t.nbytes > end {
+                end = t.offset + t.nbytes;
+                last = Some(t)
+            }
+        }
+        if len > end && !truncated {
+            reports.push((base + end, TrailingBytes { len: len - end }))
+        }
+        for (offset, kind) in reports {
+            self.report(offset, kind)
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_validate() {
+    use crate::{GGufError, GGufFileHeader, GGufWriter};
+    use GGufDiagnosticKind::*;
+
+    let mut file = Vec::new();
+    let mut w = GGufWriter::new(&mut file);
+    w.write_header(GGufFileHeader::new(3, 4, 1)).unwrap();
+    w.write_alignment(32).unwrap();
+    w.write_tensor_info("a", &[8], GGmlType::F32, 0).unwrap();
+    w.write_tensor_info("b", &[33], GGmlType::Q8_0, 64).unwrap();
+    w.write_tensor_info("c", &[4], GGmlType::F32, 16).unwrap();
+    w.write_tensor_info("d", &[4], GGmlType::F32, 96).unwrap();
+    w.write_padding(32).unwrap();
+    w.write_data(&[0; 128]).unwrap();
+    drop(w);
+
+    let diagnostics = GGuf::validate(&file);
+    let kinds = diagnostics.iter().map(|d| &d.kind).collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [
+            &IndivisibleShape {
+                name: "b".into(),
+                ty: GGmlType::Q8_0,
+                dim: 33
+            },
+            &MisalignedTensor {
+                name: "c".into(),
+                alignment: 32
+            },
+            &OverlappingTensors {
+                name: "c".into(),
+                other: "a".into()
+            },
+            &Gap { len: 64 },
+            &TrailingBytes { len: 16 },
+        ]
+    );
+    assert!(diagnostics.windows(2).all(|w| w[0].offset <= w[1].offset));
+    assert!(diagnostics[0].is_error());
+    assert!(!diagnostics[4].is_error());
+
+    // 截断的文件
+    let diagnostics = GGuf::validate(&file[..file.len() - 32]);
+    assert!(diagnostics
+        .iter()
+        .any(|d| d.kind == TensorOutOfBounds { name: "d".into() }));
+    assert!(!diagnostics
+        .iter()
+        .any(|d| matches!(d.kind, TrailingBytes { .. })));
+
+    assert!(matches!(GGuf::new(&file), Err(GGufError::IndivisibleShape(name)) if name == "b"));
+
+    assert!(matches!(
+        &*GGuf::validate(&file[..20]),
+        [GGufDiagnostic {
+            kind: Reading(GGufReadError::Eos),
+            ..
+        }]
+    ));
+}
@@ -0,0 +1,213 @@
+use crate::{GGmlType, GGuf, GGufTensorInfo};
+use std::{error::Error, fmt};
+
+/// A zero-copy view of a tensor in a [GGuf] file.
+pub struct GGufTensorView<'a> {
+    name: &'a str,
+    info: GGufTensorInfo,
+    native_endian: bool,
+    data: &'a [u8],
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GGufTensorViewError {
+    /// The tensor is of another type, which is carried.
+    TypeMismatch(GGmlType),
+    /// The file is not in native byte order, so the data cannot be reinterpreted in place.
+    NotNativeEndian,
+    /// The data is not aligned for the requested element type.
+    Misaligned,
+}
+
+impl fmt::Display for GGufTensorViewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TypeMismatch(ty) => write!(f, "tensor type mismatch: {ty:?}"),
+            Self::NotNativeEndian => f.write_str("tensor data not in native byte order"),
+            Self::Misaligned => f.write_str("tensor data misaligned"),
+        }
+    }
+}
+
+impl Error for GGufTensorViewError {}
+
+impl<'a> GGuf<'a> {
+    /// Returns a view of the tensor named `name`, or `None` if it does not exist.
+    pub fn tensor(&self, name: &str) -> Option<GGufTensorView<'a>> {
+        let (&name, meta) = self.tensors.get_key_value(name)?;
+        let info = meta.to_info();
+        let data = &self.data[info.offset()..][..info.nbytes()];
+        Some(GGufTensorView {
+            name,
+            info,
+            native_endian: self.native_endian,
+            data,
+        })
+    }
+}
+
+impl<'a> GGufTensorView<'a> {
+    #[inline]
+    pub const fn name(&self) -> &'a str {
+        self.name
+    }
+
+    #[inline]
+    pub const fn ty(&self) -> GGmlType {
+        self.info.ty()
+    }
+
+    #[inline]
+    pub const fn shape(&self) -> &[u64] {
+        self.info.shape()
+    }
+
+    #[inline]
+    pub const fn info(&self) -> &GGufTensorInfo {
+        &self.info
+    }
+
+    #[inline]
+    pub const fn is_native_endian(&self) -> bool {
+        self.native_endian
+    }
+
+    /// Raw bytes of the tensor, in the byte order of the file.
+    #[inline]
+    pub const fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Strides in bytes of each dimension, in the same order as the shape.
+    ///
+    /// The first dimension is counted in blocks, so its stride is the size of a block.
+    pub fn strides(&self) -> Vec<usize> {
+        let size = self.ty().size();
+        let mut stride = size.type_size as usize;
+        let mut ans = Vec::with_capacity(self.shape().len());
+        for (i, &d) in self.shape().iter().enumerate() {
+            ans.push(stride);
+            stride *= if i == 0 {
+                d as usize / size.block_size as usize
+            } else {
+                d as usize
+            };
+        }
+        ans
+    }
+}
+
+#[cfg(feature = "types")]
+impl<'a> GGufTensorView<'a> {
+    /// Reinterprets the data as elements or blocks of type `T`,
+    /// which must match the tensor type, be aligned and be in native byte order.
+    pub fn as_slice<T: ggml_quants::DataBlock>(&self) -> Result<&'a [T], GGufTensorViewError> {
+        #[allow(deprecated)]
+        let layout = match self.ty() {
+            GGmlType::Q4_2 | GGmlType::Q4_3 => None,
+            GGmlType::Q4_0_4_4 | GGmlType::Q4_0_4_8 | GGmlType::Q4_0_8_8 => None,
+            ty => Some(ty.to_digit_layout()),
+        };
+        if layout != Some(T::ID) {
+            return Err(GGufTensorViewError::TypeMismatch(self.ty()));
+        }
+        if !self.native_endian && size_of::<T>() > 1 {
+            return Err(GGufTensorViewError::NotNativeEndian);
+        }
+        match unsafe { self.data.align_to::<T>() } {
+            ([], data, []) => Ok(data),
+            _ => Err(GGufTensorViewError::Misaligned),
+        }
+    }
+
+    #[inline]
+    pub fn as_f32(&self) -> Result<&'a [f32], GGufTensorViewError> {
+        self.as_slice()
+    }
+
+    #[inline]
+    pub fn as_f64(&self) -> Result<&'a [f64], GGufTensorViewError> {
+        self.as_slice()
+    }
+
+    #[inline]
+    pub fn as_f16(&self) -> Result<&'a [ggml_quants::f16], GGufTensorViewError> {
+        self.as_slice()
+    }
+
+    #[inline]
+    pub fn as_bf16(&self) -> Result<&'a [ggml_quants::bf16], GGufTensorViewError> {
+        self.as_slice()
+    }
+
+    #[inline]
+    pub fn as_i8(&self) -> Result<&'a [i8], GGufTensorViewError> {
+        self.as_slice()
+    }
+
+    #[inline]
+    pub fn as_i16(&self) -> Result<&'a [i16], GGufTensorViewError> {
+        self.as_slice()
+    }
+
+    #[inline]
+    pub fn as_i32(&self) -> Result<&'a [i32], GGufTensorViewError> {
+        self.as_slice()
+    }
+
+    #[inline]
+    pub fn as_i64(&self) -> Result<&'a [i64], GGufTensorViewError> {
+        self.as_slice()
+    }
+}
+
+#[cfg(feature = "types")]
+#[test]
+fn test_tensor_view() {
+    use crate::{GGufDocument, GGufTensorBuf};
+    use ggml_quants::{f16, Q8_0};
+
+    let mut doc = GGufDocument::new();
+    let f32s = (0..8)
+        .flat_map(|x| (x as f32).to_ne_bytes())
+        .collect::<Vec<_>>();
+    for (name, ty, shape, data) in [
+        ("a", GGmlType::F32, vec![4, 2], f32s),
+        ("b", GGmlType::Q8_0, vec![64, 3], vec![0; 34 * 6]),
+        ("c", GGmlType::F16, vec![3], vec![0; 6]),
+    ] {
+        doc.insert_tensor(
+            name,
+            GGufTensorBuf {
+                ty,
+                shape,
+                data: data.into(),
+            },
+        );
+    }
+    let mut file = Vec::new();
+    doc.write(&mut file).unwrap();
+    // 复制到对齐的缓冲区
+    let mut buf = vec![0u64; file.len().div_ceil(8)];
+    let bytes =
+        unsafe { std::slice::from_raw_parts_mut(buf.as_mut_ptr().cast::<u8>(), file.len()) };
+    bytes.copy_from_slice(&file);
+    let gguf = GGuf::new(bytes).unwrap();
+
+    let a = gguf.tensor("a").unwrap();
+    assert_eq!(a.name(), "a");
+    assert_eq!(a.strides(), [4, 16]);
+    assert_eq!(a.as_f32().unwrap(), [0., 1., 2., 3., 4., 5., 6., 7.]);
+    assert_eq!(
+        a.as_i32(),
+        Err(GGufTensorViewError::TypeMismatch(GGmlType::F32))
+    );
+
+    let b = gguf.tensor("b").unwrap();
+    assert_eq!(b.strides(), [34, 68]);
+    assert_eq!(b.as_slice::<Q8_0>().unwrap().len(), 6);
+
+    let c = gguf.tensor("c").unwrap();
+    assert_eq!(c.as_f16().unwrap(), [f16::ZERO; 3]);
+    assert!(gguf.tensor("d").is_none());
+}
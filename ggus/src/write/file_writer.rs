@@ -31,7 +31,15 @@ impl<T: Borrow<[u8]>> DataFuture for T {
 impl<T: Write> GGufFileWriter<T> {
     #[inline]
     pub fn new(writer: T, header: GGufFileHeader) -> Result<Self> {
-        let mut writer = GGufWriter::new(writer);
+        Self::with_endian(writer, header, true)
+    }
+
+    /// Creates a file writer in native byte order if `native_endian` is true, or in the opposite byte order otherwise.
+    ///
+    /// Metadata values and tensor data are written as is, so they must already be in the target byte order.
+    #[inline]
+    pub fn with_endian(writer: T, header: GGufFileHeader, native_endian: bool) -> Result<Self> {
+        let mut writer = GGufWriter::with_endian(writer, native_endian);
         writer.write_header(header)?;
         Ok(Self {
             writer,
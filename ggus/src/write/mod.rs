@@ -1,7 +1,9 @@
 ﻿mod file_writer;
+mod seek_writer;
 mod simulator;
 mod writer;
 
 pub use file_writer::{DataFuture, GGufFileWriter, GGufTensorWriter};
+pub use seek_writer::{GGufSeekDataWriter, GGufSeekTensorWriter, GGufSeekWriter};
 pub use simulator::{GGufFileSimulator, GGufTensorSimulator};
 pub use writer::GGufWriter;
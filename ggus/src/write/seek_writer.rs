@@ -0,0 +1,275 @@
+use super::GGufWriter;
+use crate::{pad, GGmlType, GGufFileHeader, GGufMetaDataValueType, DEFAULT_ALIGNMENT};
+use std::{
+    collections::VecDeque,
+    io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write},
+};
+
+/// A writer over a seekable stream that does not need to know the numbers of
+/// metadata entries and tensors in advance, the header is patched when the file is finished.
+///
+/// Metadata is written first, then all tensor infos, then the data of each tensor in the same order.
+/// Tensor data is streamed to the writer as it is given, so only the sizes of the tensors are kept.
+pub struct GGufSeekWriter<T: Write + Seek> {
+    state: State<T>,
+}
+
+pub struct GGufSeekTensorWriter<T: Write + Seek> {
+    state: State<T>,
+    offset: usize,
+    sizes: VecDeque<usize>,
+}
+
+pub struct GGufSeekDataWriter<T: Write + Seek> {
+    state: State<T>,
+    sizes: VecDeque<usize>,
+}
+
+struct State<T: Write + Seek> {
+    writer: GGufWriter<T>,
+    start: u64,
+    alignment: usize,
+    n_meta_kvs: u64,
+    n_tensors: u64,
+}
+
+impl<T: Write + Seek> GGufSeekWriter<T> {
+    #[inline]
+    pub fn new(writer: T) -> Result<Self> {
+        Self::with_endian(writer, true)
+    }
+
+    /// Creates a writer in native byte order if `native_endian` is true, or in the opposite byte order otherwise.
+    ///
+    /// Metadata values and tensor data are written as is, so they must already be in the target byte order.
+    pub fn with_endian(mut writer: T, native_endian: bool) -> Result<Self> {
+        let start = writer.stream_position()?;
+        let mut writer = GGufWriter::with_endian(writer, native_endian);
+        // 先占位，结束时回填
+        writer.write_header(GGufFileHeader::new(3, 0, 0))?;
+        Ok(Self {
+            state: State {
+                writer,
+                start,
+                alignment: DEFAULT_ALIGNMENT,
+                n_meta_kvs: 0,
+                n_tensors: 0,
+            },
+        })
+    }
+
+    #[inline]
+    pub fn write_alignment(&mut self, alignment: usize) -> Result<()> {
+        self.state.writer.write_alignment(alignment)?;
+        self.state.alignment = alignment;
+        self.state.n_meta_kvs += 1;
+        Ok(())
+    }
+
+    pub fn write_meta_kv(
+        &mut self,
+        key: &str,
+        ty: GGufMetaDataValueType,
+        val: &[u8],
+    ) -> Result<()> {
+        if let Some(alignment) = self.state.writer.write_meta_kv(key, ty, val)? {
+            self.state.alignment = alignment
+        }
+        self.state.n_meta_kvs += 1;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn finish(self) -> GGufSeekTensorWriter<T> {
+        GGufSeekTensorWriter {
+            state: self.state,
+            offset: 0,
+            sizes: VecDeque::new(),
+        }
+    }
+}
+
+impl<T: Write + Seek> GGufSeekTensorWriter<T> {
+    pub fn write_tensor_info(&mut self, name: &str, ty: GGmlType, shape: &[u64]) -> Result<()> {
+        let nbytes = ty.size().checked_elements_to_bytes(shape).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("shape {shape:?} of tensor {name} is not divisible by {ty:?}"),
+            )
+        })?;
+        self.offset += pad(self.offset, self.state.alignment);
+        self.state
+            .writer
+            .write_tensor_info(name, shape, ty, self.offset as _)?;
+        self.offset += nbytes;
+        self.sizes.push_back(nbytes);
+        self.state.n_tensors += 1;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn finish(self) -> GGufSeekDataWriter<T> {
+        GGufSeekDataWriter {
+            state: self.state,
+            sizes: self.sizes,
+        }
+    }
+}
+
+impl<T: Write + Seek> GGufSeekDataWriter<T> {
+    /// Number of tensors whose data is not written yet.
+    #[inline]
+    pub fn remaining_tensors(&self) -> usize {
+        self.sizes.len()
+    }
+
+    /// Copies the data of the next tensor from `reader`, which must provide at least as many bytes as the tensor has.
+    pub fn write_data_from(&mut self, reader: impl Read) -> Result<()> {
+        let len = self.next_tensor()?;
+        let copied = std::io::copy(
+            &mut reader.take(len as _),
+            &mut Sink(&mut self.state.writer),
+        )?;
+        if copied as usize != len {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("tensor data too short, {copied} of {len} bytes"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Writes the data of the next tensor chunk by chunk, the total length of the chunks must match the tensor.
+    pub fn write_data_chunks<I>(&mut self, chunks: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let len = self.next_tensor()?;
+        let mut written = 0;
+        for chunk in chunks {
+            let chunk = chunk.as_ref();
+            written += chunk.len();
+            if written > len {
+                return Err(length_mismatch(len));
+            }
+            self.state.writer.write_data(chunk)?
+        }
+        if written != len {
+            return Err(length_mismatch(len));
+        }
+        Ok(())
+    }
+
+    /// Patches the header, returns the number of bytes written.
+    ///
+    /// The stream is left at the end of the written file.
+    pub fn finish(self) -> Result<usize> {
+        let Self { state, sizes } = self;
+        if !sizes.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("data of {} tensors not written", sizes.len()),
+            ));
+        }
+        let State {
+            mut writer,
+            start,
+            n_meta_kvs,
+            n_tensors,
+            ..
+        } = state;
+
+        let len = writer.written_bytes();
+        let mut header = Vec::with_capacity(size_of::<GGufFileHeader>());
+        GGufWriter::with_endian(&mut header, writer.is_native_endian())
+            .write_header(GGufFileHeader::new(3, n_tensors, n_meta_kvs))?;
+
+        let inner = writer.flushed_inner()?;
+        inner.seek(SeekFrom::Start(start))?;
+        inner.write_all(&header)?;
+        inner.seek(SeekFrom::Start(start + len as u64))?;
+        Ok(len)
+    }
+
+    fn next_tensor(&mut self) -> Result<usize> {
+        let len = self
+            .sizes
+            .pop_front()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "no more tensors to write"))?;
+        self.state.writer.write_padding(self.state.alignment)?;
+        Ok(len)
+    }
+}
+
+fn length_mismatch(len: usize) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("tensor data length mismatch, expected {len} bytes"),
+    )
+}
+
+/// 将 [GGufWriter] 适配为 [Write]，以便使用 [std::io::copy]
+struct Sink<'a, T: Write>(&'a mut GGufWriter<T>);
+
+impl<T: Write> Write for Sink<'_, T> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.0.write_data(buf)?;
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_seek_writer() {
+    use crate::{GGuf, GGufMetaMapExt, GGufWriter};
+    use std::io::Cursor;
+
+    let data = (0..8)
+        .flat_map(|x| (x as f32).to_ne_bytes())
+        .collect::<Vec<_>>();
+    let mut name = Vec::new();
+    GGufWriter::new(&mut name).write_str("tiny").unwrap();
+
+    let mut file = Cursor::new(Vec::new());
+    let mut writer = GGufSeekWriter::new(&mut file).unwrap();
+    writer.write_alignment(64).unwrap();
+    writer
+        .write_meta_kv("general.name", GGufMetaDataValueType::String, &name)
+        .unwrap();
+
+    let mut writer = writer.finish();
+    writer
+        .write_tensor_info("a", GGmlType::F32, &[4, 2])
+        .unwrap();
+    writer.write_tensor_info("b", GGmlType::F32, &[3]).unwrap();
+    assert!(writer
+        .write_tensor_info("c", GGmlType::Q8_0, &[33])
+        .is_err());
+
+    let mut writer = writer.finish();
+    writer.write_data_chunks(data.chunks(12)).unwrap();
+    writer.write_data_from(&data[..]).unwrap();
+    assert_eq!(writer.remaining_tensors(), 0);
+    assert!(writer.write_data_chunks([&data]).is_err());
+    let len = writer.finish().unwrap();
+
+    let file = file.into_inner();
+    assert_eq!(len, file.len());
+    let gguf = GGuf::new(&file).unwrap();
+    assert_eq!(gguf.header.metadata_kv_count, 2);
+    assert_eq!(gguf.header.tensor_count, 2);
+    assert_eq!(gguf.alignment, 64);
+    assert_eq!(gguf.get_str("general.name").unwrap(), "tiny");
+    let tensor_data = |name: &str| {
+        let info = gguf.tensors[name].to_info();
+        &gguf.data[info.offset()..][..info.nbytes()]
+    };
+    assert_eq!(tensor_data("a"), data);
+    assert_eq!(tensor_data("b"), &data[..12]);
+}
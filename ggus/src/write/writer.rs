@@ -5,13 +5,24 @@ use std::{
     slice::from_raw_parts,
 };
 
-#[repr(transparent)]
-pub struct GGufWriter<T: Write>(Internal<T>);
+pub struct GGufWriter<T: Write>(Internal<T>, bool);
 
 impl<T: Write> GGufWriter<T> {
     #[inline]
     pub fn new(writer: T) -> Self {
-        Self(Internal::new(writer))
+        Self::with_endian(writer, true)
+    }
+
+    /// Creates a writer that writes in native byte order if `native_endian` is true,
+    /// or in the opposite byte order otherwise.
+    #[inline]
+    pub fn with_endian(writer: T, native_endian: bool) -> Self {
+        Self(Internal::new(writer), native_endian)
+    }
+
+    #[inline]
+    pub const fn is_native_endian(&self) -> bool {
+        self.1
     }
 
     #[inline]
@@ -19,8 +30,15 @@ impl<T: Write> GGufWriter<T> {
         self.0.written_bytes()
     }
 
+    /// Flushes buffered data and returns the underlying writer.
+    #[inline]
+    pub(crate) fn flushed_inner(&mut self) -> Result<&mut T> {
+        self.0.flushed_inner()
+    }
+
     pub fn write_header(&mut self, header: GGufFileHeader) -> Result<()> {
-        self.write(unsafe {
+        let header = if self.1 { header } else { header.swap_bytes() };
+        self.0.write_bytes(unsafe {
             from_raw_parts(
                 &header as *const _ as *const u8,
                 size_of::<GGufFileHeader>(),
@@ -28,9 +46,18 @@ impl<T: Write> GGufWriter<T> {
         })
     }
 
+    /// Writes scalar values, multi-byte values are byte-swapped one by one if the writer is not in native byte order.
     pub fn write<U: Copy + 'static>(&mut self, val: &[U]) -> Result<()> {
-        self.0
-            .write_bytes(unsafe { from_raw_parts(val.as_ptr().cast(), size_of_val(val)) })
+        let bytes = unsafe { from_raw_parts(val.as_ptr().cast::<u8>(), size_of_val(val)) };
+        if self.1 || size_of::<U>() == 1 {
+            self.0.write_bytes(bytes)
+        } else {
+            let mut bytes = bytes.to_vec();
+            for ele in bytes.chunks_exact_mut(size_of::<U>()) {
+                ele.reverse()
+            }
+            self.0.write_bytes(&bytes)
+        }
     }
 
     pub fn write_str(&mut self, val: impl AsRef<str>) -> Result<()> {
@@ -43,7 +70,7 @@ impl<T: Write> GGufWriter<T> {
         self.write_meta_kv(
             GENERAL_ALIGNMENT,
             GGufMetaDataValueType::U32,
-            &(alignment as u32).to_le_bytes(),
+            &self.order_u32(alignment as _).to_ne_bytes(),
         )?;
         Ok(())
     }
@@ -62,7 +89,7 @@ impl<T: Write> GGufWriter<T> {
             let &[a, b, c, d] = val else {
                 panic!("general.alignment must be an u32")
             };
-            Some(u32::from_le_bytes([a, b, c, d]) as _)
+            Some(self.order_u32(u32::from_ne_bytes([a, b, c, d])) as _)
         } else {
             None
         })
@@ -92,6 +119,16 @@ impl<T: Write> GGufWriter<T> {
     pub fn write_data(&mut self, data: &[u8]) -> Result<()> {
         self.write(data)
     }
+
+    /// 在本机字节序与写入字节序之间转换
+    #[inline]
+    const fn order_u32(&self, val: u32) -> u32 {
+        if self.1 {
+            val
+        } else {
+            val.swap_bytes()
+        }
+    }
 }
 
 mod internal {
@@ -110,6 +147,12 @@ mod internal {
             self.1
         }
 
+        #[inline]
+        pub fn flushed_inner(&mut self) -> Result<&mut T> {
+            self.0.flush()?;
+            Ok(self.0.get_mut())
+        }
+
         #[inline]
         pub fn write_bytes(&mut self, val: &[u8]) -> Result<()> {
             self.1 += val.len();
@@ -1,9 +1,8 @@
 ﻿use crate::{
     utils::OutputArgs,
-    utils::{operate, show_file_info, Operator},
+    utils::{operate, parse_file_name, show_file_info, Operator},
     LogArgs,
 };
-use ggus::GGufFileName;
 use std::path::PathBuf;
 
 #[derive(Args, Default)]
@@ -29,7 +28,7 @@ impl CastArgs {
         } = self;
         log.init();
 
-        let name = GGufFileName::try_from(&*file).unwrap();
+        let name = parse_file_name(&file);
         let dir = file.parent().unwrap();
         let files = operate(
             name.clone(),
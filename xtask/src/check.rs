@@ -0,0 +1,129 @@
+use crate::LogArgs;
+use ggus::{GGuf, GGufMetaMapExt, GGufModel};
+use memmap2::Mmap;
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    process::exit,
+};
+
+#[derive(Args, Default)]
+pub struct CheckArgs {
+    /// The files to check
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
+    /// If set, check all shards of each file in its directory
+    #[clap(long)]
+    shards: bool,
+    /// Treat warnings as errors
+    #[clap(long)]
+    deny_warnings: bool,
+    /// Also check that each model has the meta keys and tensors its architecture needs in llama.cpp
+    #[clap(long)]
+    arch: bool,
+
+    #[clap(flatten)]
+    log: LogArgs,
+}
+
+impl CheckArgs {
+    pub fn check(self) {
+        let Self {
+            files,
+            shards,
+            deny_warnings,
+            arch,
+            log,
+        } = self;
+        log.init();
+
+        let models = if arch { files.clone() } else { Vec::new() };
+
+        let files = if shards {
+            files
+                .into_iter()
+                .flat_map(|file| GGufModel::shard_paths(&file).unwrap())
+                .collect()
+        } else {
+            files
+        };
+
+        let mut failed = 0;
+        for path in &files {
+            let file = match File::open(path).and_then(|f| unsafe { Mmap::map(&f) }) {
+                Ok(m) => m,
+                Err(e) => {
+                    println!("{}: error: failed to open file: {e}", path.display());
+                    failed += 1;
+                    continue;
+                }
+            };
+
+            let diagnostics = GGuf::validate(&file);
+            for d in &diagnostics {
+                println!("{}: {d}", path.display())
+            }
+            let errors = diagnostics
+                .iter()
+                .filter(|d| deny_warnings || d.is_error())
+                .count();
+            if errors > 0 {
+                failed += 1
+            }
+        }
+
+        println!("{} files checked, {failed} failed", files.len());
+
+        let mut failed_models = 0;
+        for path in &models {
+            if !check_arch(path) {
+                failed_models += 1
+            }
+        }
+        if arch {
+            println!("{} models checked, {failed_models} failed", models.len());
+        }
+
+        if failed > 0 || failed_models > 0 {
+            exit(1)
+        }
+    }
+}
+
+/// 检查模型能否按其架构加载，返回是否通过
+fn check_arch(path: &Path) -> bool {
+    let result = GGufModel::shard_paths(path)
+        .map_err(|e| e.to_string())
+        .and_then(|paths| {
+            paths
+                .iter()
+                .map(|p| File::open(p).and_then(|f| unsafe { Mmap::map(&f) }))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("failed to open file: {e}"))
+        });
+    let files = match result {
+        Ok(files) => files,
+        Err(e) => {
+            println!("{}: error: {e}", path.display());
+            return false;
+        }
+    };
+    let model = match GGufModel::new(files.iter().map(|m| &**m)) {
+        Ok(model) => model,
+        Err(e) => {
+            println!("{}: error: {e}", path.display());
+            return false;
+        }
+    };
+
+    let issues = model.check_arch(
+        model
+            .tensors
+            .iter()
+            .map(|(name, t)| (name, t.meta.to_info().shape().to_vec())),
+    );
+    for issue in &issues {
+        println!("{}: error: {issue}", path.display())
+    }
+    issues.is_empty()
+}
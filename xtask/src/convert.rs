@@ -1,17 +1,17 @@
 ﻿use crate::{
     utils::OutputArgs,
-    utils::{operate, show_file_info, Operator},
+    utils::{operate, parse_file_name, show_file_info, Operator},
     LogArgs,
 };
-use ggus::GGufFileName;
 use std::path::PathBuf;
 
 #[derive(Args, Default)]
 pub struct ConvertArgs {
     /// File to convert
     file: PathBuf,
-    /// Steps to apply, separated by "->", maybe "sort", "merge-linear", "split-linear", "filter-meta:<key>" or "filter-tensor:<name>"
-    #[clap(long, short = 'x')]
+    /// Steps to apply, separated by "->", maybe "sort", "merge-linear", "split-linear", "filter-meta:<key>", "filter-tensor:<name>" or "endian:<little|big|native>".
+    /// Files are always written in the latest GGUF version, so no step is needed to upgrade legacy files
+    #[clap(long, short = 'x', default_value = "")]
     steps: String,
 
     #[clap(flatten)]
@@ -30,21 +30,26 @@ impl ConvertArgs {
         } = self;
         log.init();
 
-        let name = GGufFileName::try_from(&*file).unwrap();
+        let name = parse_file_name(&file);
         let dir = file.parent().unwrap();
         let files = operate(
             name.clone(),
             name.iter_all().map(|name| dir.join(name.to_string())),
-            steps.split("->").map(|op| match op.trim() {
-                "sort" => Operator::SortTensors,
-                "merge-linear" => Operator::MergeLinear(true),
-                "split-linear" | "!merge-linear" => Operator::MergeLinear(false),
-                op => match op.split_once(':') {
-                    Some(("filter-meta", key)) => Operator::filter_meta_key(key),
-                    Some(("filter-tensor", name)) => Operator::filter_tensor_name(name),
-                    _ => panic!("Unsupported operation: {op}"),
-                },
-            }),
+            steps
+                .split("->")
+                .map(str::trim)
+                .filter(|op| !op.is_empty())
+                .map(|op| match op {
+                    "sort" => Operator::SortTensors,
+                    "merge-linear" => Operator::MergeLinear(true),
+                    "split-linear" | "!merge-linear" => Operator::MergeLinear(false),
+                    op => match op.split_once(':') {
+                        Some(("filter-meta", key)) => Operator::filter_meta_key(key),
+                        Some(("filter-tensor", name)) => Operator::filter_tensor_name(name),
+                        Some(("endian", endian)) => Operator::endian(endian),
+                        _ => panic!("Unsupported operation: {op}"),
+                    },
+                }),
             output.into(),
         )
         .unwrap();
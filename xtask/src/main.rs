@@ -1,8 +1,10 @@
 #![deny(warnings)]
 
 mod cast;
+mod check;
 mod convert;
 mod merge;
+mod rename;
 mod set_meta;
 mod show;
 mod split;
@@ -17,12 +19,14 @@ fn main() {
     use Commands::*;
     match Cli::parse().command {
         Show(args) => args.show(),
+        Check(args) => args.check(),
         Split(args) => args.split(),
         Merge(args) => args.merge(),
         Cast(args) => args.cast(),
         Convert(args) => args.convert(),
         ToLlama(args) => args.convert_to_llama(),
         SetMeta(args) => args.set_meta(),
+        Rename(args) => args.rename(),
     }
 }
 
@@ -38,6 +42,8 @@ struct Cli {
 enum Commands {
     /// Show the contents of gguf files
     Show(show::ShowArgs),
+    /// Check the structure of gguf files
+    Check(check::CheckArgs),
     /// Split gguf files into shards
     Split(split::SplitArgs),
     /// Merge shards into a single gguf file
@@ -50,6 +56,8 @@ enum Commands {
     ToLlama(to_llama::ToLlamaArgs),
     /// Set metadata of gguf files
     SetMeta(set_meta::SetMetaArgs),
+    /// Rename gguf files by their metadata
+    Rename(rename::RenameArgs),
 }
 
 #[derive(Args, Default)]
@@ -1,9 +1,8 @@
 ﻿use crate::{
-    utils::{operate, show_file_info, OutputConfig},
+    utils::{operate, parse_file_name, show_file_info, OutputConfig},
     LogArgs,
 };
-use ggus::GGufFileName;
-use std::{ops::Deref, path::PathBuf};
+use std::path::PathBuf;
 
 #[derive(Args, Default)]
 pub struct MergeArgs {
@@ -27,7 +26,7 @@ impl MergeArgs {
         log.init();
 
         let dir = file.parent().unwrap();
-        let name: GGufFileName = file.deref().try_into().unwrap();
+        let name = parse_file_name(&file);
         if name.shard_count() == 1 {
             println!("Model does not need to merge.");
             return;
@@ -0,0 +1,54 @@
+use crate::{utils::shard_paths, LogArgs};
+use ggus::{GGufFileName, GGufModel};
+use memmap2::Mmap;
+use std::{fs, iter::zip, path::PathBuf};
+
+#[derive(Args, Default)]
+pub struct RenameArgs {
+    /// The file to rename, all shards of it are renamed together
+    file: PathBuf,
+    /// If set, only show the new names
+    #[clap(long)]
+    dry_run: bool,
+
+    #[clap(flatten)]
+    log: LogArgs,
+}
+
+impl RenameArgs {
+    pub fn rename(self) {
+        let Self { file, dry_run, log } = self;
+        log.init();
+
+        let paths = shard_paths(&file);
+        let names = {
+            let files = paths
+                .iter()
+                .map(|path| fs::File::open(path).and_then(|f| unsafe { Mmap::map(&f) }))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            let model = GGufModel::new(files.iter().map(|m| &**m)).unwrap();
+            let tensors = model.tensors.iter().map(|(&name, tensor)| {
+                let info = tensor.meta.to_info();
+                (name, info.ty(), info.shape().to_vec())
+            });
+            GGufFileName::from_meta(&model, tensors)
+                .split_n(paths.len())
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        for (path, name) in zip(paths, names) {
+            let new = path.with_file_name(&name);
+            if new == path {
+                println!("{} unchanged", path.display());
+                continue;
+            }
+            println!("{} -> {name}", path.display());
+            if !dry_run {
+                assert!(!new.exists(), "{} already exists", new.display());
+                fs::rename(&path, &new).unwrap()
+            }
+        }
+    }
+}
@@ -1,11 +1,11 @@
 ﻿use crate::{
-    utils::{operate, show_file_info, Operator, OutputArgs},
+    utils::{operate, parse_file_name, plan_meta_patch, show_file_info, Operator, OutputArgs},
     LogArgs,
 };
-use ggus::GGufFileName;
 use std::{
-    fs::read_to_string,
+    fs::{read_to_string, OpenOptions},
     path::{Path, PathBuf},
+    process::exit,
 };
 
 #[derive(Args, Default)]
@@ -14,6 +14,12 @@ pub struct SetMetaArgs {
     file: PathBuf,
     /// Meta data to set for the file
     meta_kvs: String,
+    /// Patch the file in place without rewriting tensor data, if the new metadata fits
+    #[clap(long)]
+    in_place: bool,
+    /// With `--in-place`, only report the byte budget without writing
+    #[clap(long, requires = "in_place")]
+    dry_run: bool,
 
     #[clap(flatten)]
     output: OutputArgs,
@@ -26,6 +32,8 @@ impl SetMetaArgs {
         let Self {
             file,
             meta_kvs,
+            in_place,
+            dry_run,
             output,
             log,
         } = self;
@@ -44,8 +52,25 @@ impl SetMetaArgs {
             meta_kvs
         };
 
+        if in_place {
+            let patch =
+                plan_meta_patch(&file, [Operator::set_meta_by_cfg(&cfg)]).unwrap_or_else(|e| {
+                    eprintln!("{}: {e:?}", file.display());
+                    exit(1)
+                });
+            println!("{}: {patch}", file.display());
+            if !patch.fits() {
+                exit(1)
+            }
+            if !dry_run {
+                let file = OpenOptions::new().write(true).open(&file).unwrap();
+                patch.write_to(file).unwrap();
+            }
+            return;
+        }
+
         let files = operate(
-            GGufFileName::try_from(&*file).unwrap(),
+            parse_file_name(&file),
             [&file],
             [Operator::set_meta_by_cfg(&cfg)],
             output.into(),
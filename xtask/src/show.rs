@@ -1,7 +1,8 @@
-﻿use crate::{utils::compile_patterns, LogArgs};
-use ggus::{
-    GGufFileHeader, GGufFileName, GGufMetaDataValueType, GGufMetaKV, GGufReadError, GGufReader,
+﻿use crate::{
+    utils::{compile_patterns, shard_paths},
+    LogArgs,
 };
+use ggus::{GGufFileHeader, GGufMetaDataValueType, GGufMetaKV, GGufReadError, GGufReader};
 use indexmap::IndexMap;
 use memmap2::Mmap;
 use regex::Regex;
@@ -59,12 +60,7 @@ impl ShowArgs {
         let filter_tensor = compile_patterns(&filter_tensor);
 
         let files = if shards {
-            let dir = file.parent().unwrap();
-            GGufFileName::try_from(&*file)
-                .unwrap()
-                .iter_all()
-                .map(|name| dir.join(name.to_string()))
-                .collect::<Vec<_>>()
+            shard_paths(&file)
         } else {
             vec![file]
         };
@@ -140,20 +136,13 @@ fn show_header(reader: &mut GGufReader) -> Result<GGufFileHeader, Failed> {
         println!("{ERR}Magic   = {:?}", header.magic());
         return Err(Failed);
     }
-    let native_endian = if u16::from_le(1) == 1 {
+    let endian = if cfg!(target_endian = "little") == reader.is_native_endian() {
         "Little"
-    } else if u16::from_be(1) == 1 {
-        "Big"
     } else {
-        "Unknown"
+        "Big"
     };
-    if header.is_native_endian() {
-        println!("{YES}Endian  = {native_endian}");
-    } else {
-        println!("{ERR}Endian  = {native_endian}");
-        return Err(Failed);
-    }
-    if header.version == 3 {
+    println!("{YES}Endian  = {endian}");
+    if (1..=3).contains(&header.version) {
         println!("{YES}Version = {}", header.version);
     } else {
         println!("{ERR}Version = {}", header.version);
@@ -316,7 +305,7 @@ fn fmt_meta_val(
                 T::I64 => buf.push_str(&reader.read::<i64>()?.to_string()),
                 T::F32 => buf.push_str(&fmt_exp(reader.read::<f32>()?)),
                 T::F64 => buf.push_str(&fmt_exp(reader.read::<f64>()? as _)),
-                T::Bool => buf.push(if reader.read()? { '√' } else { '×' }),
+                T::Bool => buf.push(if reader.read_bool()? { '√' } else { '×' }),
                 T::String => {
                     let str = reader.read_str()?;
                     if str.lines().nth(1).is_some() {
@@ -1,9 +1,8 @@
 ﻿use crate::{
-    utils::{operate, show_file_info, OutputArgs},
+    utils::{operate, parse_file_name, show_file_info, OutputArgs},
     LogArgs,
 };
-use ggus::GGufFileName;
-use std::{ops::Deref, path::PathBuf};
+use std::path::PathBuf;
 
 #[derive(Args, Default)]
 pub struct SplitArgs {
@@ -21,7 +20,7 @@ impl SplitArgs {
         let Self { file, output, log } = self;
         log.init();
 
-        let name: GGufFileName = file.deref().try_into().unwrap();
+        let name = parse_file_name(&file);
         if name.shard_count() > 1 {
             println!("Model has already been splited");
             return;
@@ -1,8 +1,7 @@
 ﻿use crate::{
-    utils::{operate, show_file_info, Operator, OutputArgs},
+    utils::{operate, parse_file_name, show_file_info, Operator, OutputArgs},
     LogArgs,
 };
-use ggus::GGufFileName;
 use std::path::PathBuf;
 
 #[derive(Args, Default)]
@@ -29,7 +28,7 @@ impl ToLlamaArgs {
         } = self;
         log.init();
 
-        let name = GGufFileName::try_from(&*file).unwrap();
+        let name = parse_file_name(&file);
         let dir = file.parent().unwrap();
         let files = operate(
             name.clone(),
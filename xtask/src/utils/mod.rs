@@ -6,16 +6,15 @@ mod read;
 mod write;
 
 use file_info::FileInfo;
-use ggus::{GGmlType, GGufError, GGufFileName, GGufMetaDataValueType, GGufMetaMap, GGufWriter};
-use indexmap::IndexMap;
+use ggus::{GGufDocument, GGufError, GGufFileName, GGufMetaPatch, GGufModel};
 use log::info;
-use memmap2::{Mmap, MmapMut};
+use memmap2::Mmap;
 use std::{
-    borrow::Cow,
     fs::File,
     io,
-    path::Path,
-    sync::{Arc, LazyLock},
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    process::exit,
     time::Instant,
 };
 
@@ -24,11 +23,32 @@ pub(crate) use name_pattern::compile_patterns;
 pub(crate) use operator::Operator;
 pub(crate) use output::{OutputArgs, OutputConfig};
 
+/// 解析 GGuf 文件名，不合规范时打印错误并退出
+pub(crate) fn parse_file_name(path: &Path) -> GGufFileName<'_> {
+    GGufFileName::try_from(path).unwrap_or_else(|e| {
+        eprintln!("{}: {e}", path.display());
+        exit(1)
+    })
+}
+
+/// 列出模型所有分片的路径，文件名不合规范时打印错误并退出
+pub(crate) fn shard_paths(path: &Path) -> Vec<PathBuf> {
+    GGufModel::shard_paths(path).unwrap_or_else(|e| {
+        eprintln!("{}: {e}", path.display());
+        exit(1)
+    })
+}
+
 #[allow(dead_code)]
 #[derive(Debug)]
 pub(crate) enum OperateError {
     GGuf(GGufError),
     Io(io::Error),
+    /// 原地写回时对齐被修改
+    AlignmentChanged {
+        from: usize,
+        to: usize,
+    },
 }
 
 pub(crate) fn operate<T: AsRef<Path>>(
@@ -56,72 +76,96 @@ pub(crate) fn operate<T: AsRef<Path>>(
     ans
 }
 
-struct Content<'a> {
-    name: GGufFileName<'a>,
-    alignment: usize,
-    meta_kvs: IndexMap<Cow<'a, str>, MetaValue<'a>>,
-    tensors: IndexMap<Cow<'a, str>, Tensor<'a>>,
-}
+/// 对单个文件执行操作，并规划原地写回元信息，不支持修改张量和对齐，文件自身的分片信息保持不变
+pub(crate) fn plan_meta_patch(
+    path: &Path,
+    operations: impl IntoIterator<Item = Operator>,
+) -> Result<GGufMetaPatch, OperateError> {
+    let file = File::open(path)
+        .and_then(|f| unsafe { Mmap::map(&f) })
+        .map_err(OperateError::Io)?;
 
-impl GGufMetaMap for Content<'_> {
-    fn get(&self, key: &str) -> Option<(GGufMetaDataValueType, &[u8])> {
-        self.meta_kvs.get(key).map(|v| (v.ty, &*v.value))
+    let name = parse_file_name(path);
+    let mut content = Content::new(name, [&*file]).map_err(OperateError::GGuf)?;
+    let alignment = content.alignment;
+    for op in operations {
+        content.apply(op);
     }
-}
-
-struct MetaValue<'a> {
-    ty: GGufMetaDataValueType,
-    value: Cow<'a, [u8]>,
-}
-
-impl MetaValue<'_> {
-    fn string(s: &str) -> Self {
-        Self {
-            ty: GGufMetaDataValueType::String,
-            value: {
-                let mut vec = Vec::with_capacity(s.len() + size_of::<u64>());
-                GGufWriter::new(&mut vec).write_str(s).unwrap();
-                vec.into()
-            },
-        }
+    if content.alignment != alignment {
+        return Err(OperateError::AlignmentChanged {
+            from: alignment,
+            to: content.alignment,
+        });
     }
-}
 
-struct Tensor<'a> {
-    ty: GGmlType,
-    shape: Vec<u64>,
-    data: DataPromise<'a>,
+    GGufMetaPatch::new(&file, &content.meta_kvs).map_err(OperateError::GGuf)
 }
 
-#[derive(Clone)]
-enum DataPromise<'a> {
-    Borrowed(&'a [u8]),
-    Lazy(Arc<dyn LazyData + Send + Sync + 'a>),
+struct Content<'a> {
+    name: GGufFileName<'a>,
+    /// 写入文件的字节序，内容总是以本机字节序保存
+    native_endian: bool,
+    doc: GGufDocument<'a>,
 }
 
-impl<'a> DataPromise<'a> {
-    fn lazy(f: impl FnOnce() -> MmapMut + Send + Sync + 'a) -> Self {
-        Self::Lazy(Arc::new(LazyLock::new(f)))
+impl<'a> Deref for Content<'a> {
+    type Target = GGufDocument<'a>;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.doc
     }
 }
 
-impl ggus::DataFuture for DataPromise<'_> {
+impl DerefMut for Content<'_> {
     #[inline]
-    fn get(&self) -> &[u8] {
-        match self {
-            Self::Borrowed(data) => data,
-            Self::Lazy(data) => data.get(),
-        }
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.doc
     }
 }
 
-trait LazyData {
-    fn get(&self) -> &[u8];
-}
+#[test]
+fn test_patch_shard() {
+    use ggus::{
+        GGmlType, GGuf, GGufMetaBuf, GGufMetaMapExt, GGufTensorBuf, SPLIT_COUNT, SPLIT_NO,
+        SPLIT_TENSORS_COUNT,
+    };
+    use std::fs;
 
-impl<F: FnOnce() -> MmapMut> LazyData for LazyLock<MmapMut, F> {
-    #[inline]
-    fn get(&self) -> &[u8] {
-        self
+    let mut doc = GGufDocument::new();
+    doc.insert_meta_kv("general.architecture", GGufMetaBuf::string("llama"));
+    doc.insert_meta_kv("general.name", GGufMetaBuf::string(&"x".repeat(64)));
+    for name in ["a", "b"] {
+        doc.insert_tensor(
+            name,
+            GGufTensorBuf {
+                ty: GGmlType::F32,
+                shape: vec![4],
+                data: vec![1; 16].into(),
+            },
+        );
     }
+
+    let dir = std::env::temp_dir().join(format!("gguf-utils-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("Tiny-1M-v1.0-F32-00001-of-00002.gguf");
+    doc.write_shard(File::create(&path).unwrap(), 0..1, 0, 2, true)
+        .unwrap();
+
+    let patch = plan_meta_patch(
+        &path,
+        [Operator::set_meta_by_cfg(r#"'general.name' str "tiny""#)],
+    )
+    .unwrap();
+    assert!(patch.fits());
+    patch
+        .write_to(fs::OpenOptions::new().write(true).open(&path).unwrap())
+        .unwrap();
+
+    let file = fs::read(&path).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    let gguf = GGuf::new(&file).unwrap();
+    assert_eq!(gguf.get_str("general.name").unwrap(), "tiny");
+    assert_eq!(gguf.get_usize(SPLIT_NO).unwrap(), 0);
+    assert_eq!(gguf.get_usize(SPLIT_COUNT).unwrap(), 2);
+    assert_eq!(gguf.get_usize(SPLIT_TENSORS_COUNT).unwrap(), 2);
 }
@@ -1,7 +1,11 @@
-﻿use super::{Content, DataPromise, Operator};
+﻿use super::{Content, Operator};
 use ggus::{
-    ggml_quants::{bf16, f16, QuantExt, Q4_0, Q4_1, Q5_0, Q5_1, Q8_0, Q8_1},
-    DataFuture, GGmlType as Ty, GGufMetaMapExt,
+    ggml_quants::{
+        bf16, f16, QuantExt, IQ2XS, IQ2XXS, IQ4NL, IQ4XS, Q2K, Q3K, Q4K, Q4_0, Q4_1, Q5K, Q5_0,
+        Q5_1, Q6K, Q8_0, Q8_1,
+    },
+    DataFuture, GGmlType as Ty, GGufMetaMapExt, GGufTensorData, GGufTensorKind as K,
+    GGufTensorName, GGufTensorSuffix,
 };
 use log::debug;
 use memmap2::MmapMut;
@@ -31,16 +35,19 @@ impl Content<'_> {
             "llama" => {
                 let [mat, embd, norm, else_] =
                     ["mat", "embd", "norm", "else"].map(|name| types.get(name).copied());
-                self.cast_(mat, |name, shape| {
-                    if matches!(name, "token_embd.weight" | "output.weight") {
-                        embd
-                    } else if name.ends_with("_norm.weight") {
-                        norm
-                    } else if shape.len() > 1 {
-                        mat
-                    } else {
-                        else_
-                    }
+                self.cast_(mat, |name, shape| match name.parse() {
+                    Ok(GGufTensorName {
+                        kind: K::TokenEmbd | K::Output,
+                        suffix: Some(GGufTensorSuffix::Weight),
+                        ..
+                    }) => embd,
+                    Ok(GGufTensorName {
+                        kind,
+                        suffix: Some(GGufTensorSuffix::Weight),
+                        ..
+                    }) if kind.is_norm() => norm,
+                    _ if shape.len() > 1 => mat,
+                    _ => else_,
                 })
             }
             "clip" => {
@@ -77,7 +84,7 @@ impl Content<'_> {
 
                 let data = tensor.data.clone();
                 let row = tensor.shape[0];
-                tensor.data = DataPromise::lazy(move || cast(row as _, data.get(), from, to))
+                tensor.data = GGufTensorData::lazy(move || cast(row as _, data.get(), from, to))
             }
         }
     }
@@ -95,6 +102,15 @@ fn cast(row: usize, data: &[u8], from: Ty, to: Ty) -> MmapMut {
             Ty::Q5_1     => quantize::<Q5_1, f32, 32>(data, row),
             Ty::Q8_0     => quantize::<Q8_0, f32, 32>(data, row),
             Ty::Q8_1     => quantize::<Q8_1, f32, 32>(data, row),
+            Ty::Q2K      => quantize::<Q2K , f32,256>(data, row),
+            Ty::Q3K      => quantize::<Q3K , f32,256>(data, row),
+            Ty::Q4K      => quantize::<Q4K , f32,256>(data, row),
+            Ty::Q5K      => quantize::<Q5K , f32,256>(data, row),
+            Ty::Q6K      => quantize::<Q6K , f32,256>(data, row),
+            Ty::IQ4NL    => quantize::<IQ4NL, f32, 32>(data, row),
+            Ty::IQ4XS    => quantize::<IQ4XS, f32,256>(data, row),
+            Ty::IQ2XXS   => quantize::<IQ2XXS, f32,256>(data, row),
+            Ty::IQ2XS    => quantize::<IQ2XS, f32,256>(data, row),
             Ty::BF16     => quantize::<bf16, f32,  1>(data, row),
             _ => todo!(),
         },
@@ -107,6 +123,15 @@ fn cast(row: usize, data: &[u8], from: Ty, to: Ty) -> MmapMut {
             Ty::Q5_1     =>   quantize::<Q5_1, f16, 32>(data, row),
             Ty::Q8_0     =>   quantize::<Q8_0, f16, 32>(data, row),
             Ty::Q8_1     =>   quantize::<Q8_1, f16, 32>(data, row),
+            Ty::Q2K      =>   quantize::<Q2K , f16,256>(data, row),
+            Ty::Q3K      =>   quantize::<Q3K , f16,256>(data, row),
+            Ty::Q4K      =>   quantize::<Q4K , f16,256>(data, row),
+            Ty::Q5K      =>   quantize::<Q5K , f16,256>(data, row),
+            Ty::Q6K      =>   quantize::<Q6K , f16,256>(data, row),
+            Ty::IQ4NL    =>   quantize::<IQ4NL, f16, 32>(data, row),
+            Ty::IQ4XS    =>   quantize::<IQ4XS, f16,256>(data, row),
+            Ty::IQ2XXS   =>   quantize::<IQ2XXS, f16,256>(data, row),
+            Ty::IQ2XS    =>   quantize::<IQ2XS, f16,256>(data, row),
             Ty::BF16     =>   quantize::<bf16, f16,  1>(data, row),
             _ => todo!(),
         },
@@ -119,9 +144,36 @@ fn cast(row: usize, data: &[u8], from: Ty, to: Ty) -> MmapMut {
             Ty::Q5_1     =>   quantize::<Q5_1, bf16, 32>(data, row),
             Ty::Q8_0     =>   quantize::<Q8_0, bf16, 32>(data, row),
             Ty::Q8_1     =>   quantize::<Q8_1, bf16, 32>(data, row),
+            Ty::Q2K      =>   quantize::<Q2K , bf16,256>(data, row),
+            Ty::Q3K      =>   quantize::<Q3K , bf16,256>(data, row),
+            Ty::Q4K      =>   quantize::<Q4K , bf16,256>(data, row),
+            Ty::Q5K      =>   quantize::<Q5K , bf16,256>(data, row),
+            Ty::Q6K      =>   quantize::<Q6K , bf16,256>(data, row),
+            Ty::IQ4NL    =>   quantize::<IQ4NL, bf16, 32>(data, row),
+            Ty::IQ4XS    =>   quantize::<IQ4XS, bf16,256>(data, row),
+            Ty::IQ2XXS   =>   quantize::<IQ2XXS, bf16,256>(data, row),
+            Ty::IQ2XS    =>   quantize::<IQ2XS, bf16,256>(data, row),
             Ty::BF16     => unreachable!(),
             _ => todo!(),
         },
+        _ if to == Ty::F32 => match from {
+            Ty::Q4_0     => dequantize::<Q4_0, f32, 32>(data),
+            Ty::Q4_1     => dequantize::<Q4_1, f32, 32>(data),
+            Ty::Q5_0     => dequantize::<Q5_0, f32, 32>(data),
+            Ty::Q5_1     => dequantize::<Q5_1, f32, 32>(data),
+            Ty::Q8_0     => dequantize::<Q8_0, f32, 32>(data),
+            Ty::Q8_1     => dequantize::<Q8_1, f32, 32>(data),
+            Ty::Q2K      => dequantize::<Q2K , f32,256>(data),
+            Ty::Q3K      => dequantize::<Q3K , f32,256>(data),
+            Ty::Q4K      => dequantize::<Q4K , f32,256>(data),
+            Ty::Q5K      => dequantize::<Q5K , f32,256>(data),
+            Ty::Q6K      => dequantize::<Q6K , f32,256>(data),
+            Ty::IQ4NL    => dequantize::<IQ4NL, f32, 32>(data),
+            Ty::IQ4XS    => dequantize::<IQ4XS, f32,256>(data),
+            Ty::IQ2XXS   => dequantize::<IQ2XXS, f32,256>(data),
+            Ty::IQ2XS    => dequantize::<IQ2XS, f32,256>(data),
+            _ => todo!(),
+        },
         _ => cast(row, &cast(row, data, from, Ty::F32), Ty::F32, to),
     }
 }
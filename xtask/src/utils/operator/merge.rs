@@ -1,5 +1,8 @@
-﻿use super::{super::Tensor, blk_tensor_name, Content, DataPromise, BLK_TENSOR_REGEX};
-use ggus::{DataFuture, GGufMetaMapExt};
+﻿use super::Content;
+use ggus::{
+    DataFuture, GGufMetaMapExt, GGufTensorBuf, GGufTensorData, GGufTensorKind as K, GGufTensorName,
+    GGufTensorSuffix,
+};
 use memmap2::MmapMut;
 use std::borrow::Cow;
 
@@ -22,17 +25,16 @@ impl Content<'_> {
             let mut gate_up = MergeCollector::<NUM_GATE_UP>::new(blk);
 
             for (name, tensor) in tensors {
-                let Some(captures) = BLK_TENSOR_REGEX.captures(&name) else {
+                let Some((i, kind)) = blk_weight(&name) else {
                     self.tensors.insert(name, tensor);
                     continue;
                 };
-                let i = &captures[1];
-                if let Some((name, tensor)) = match &captures[2] {
-                    NAME_Q => qkv.put(tensor, i, 0),
-                    NAME_K => qkv.put(tensor, i, 1),
-                    NAME_V => qkv.put(tensor, i, 2),
-                    NAME_GATE => gate_up.put(tensor, i, 0),
-                    NAME_UP => gate_up.put(tensor, i, 1),
+                if let Some((name, tensor)) = match kind {
+                    K::AttnQ => qkv.put(tensor, i, 0),
+                    K::AttnK => qkv.put(tensor, i, 1),
+                    K::AttnV => qkv.put(tensor, i, 2),
+                    K::FfnGate => gate_up.put(tensor, i, 0),
+                    K::FfnUp => gate_up.put(tensor, i, 1),
                     _ => Some((name, tensor)),
                 } {
                     self.tensors.insert(name, tensor);
@@ -40,22 +42,21 @@ impl Content<'_> {
             }
         } else {
             for (name, tensor) in tensors {
-                let Some(captures) = BLK_TENSOR_REGEX.captures(&name) else {
+                let Some((i, kind)) = blk_weight(&name) else {
                     self.tensors.insert(name, tensor);
                     continue;
                 };
-                let i = &captures[1];
-                match &captures[2] {
-                    NAME_QKV => {
+                match kind {
+                    K::AttnQkv => {
                         let [q, k, v] = split_qkv(tensor);
-                        self.tensors.insert(blk_tensor_name(i, NAME_Q), q);
-                        self.tensors.insert(blk_tensor_name(i, NAME_K), k);
-                        self.tensors.insert(blk_tensor_name(i, NAME_V), v);
+                        self.tensors.insert(blk_weight_name(i, K::AttnQ), q);
+                        self.tensors.insert(blk_weight_name(i, K::AttnK), k);
+                        self.tensors.insert(blk_weight_name(i, K::AttnV), v);
                     }
-                    NAME_GATE_UP => {
+                    K::FfnGateUp => {
                         let [gate, up] = split_gate_up(tensor);
-                        self.tensors.insert(blk_tensor_name(i, NAME_GATE), gate);
-                        self.tensors.insert(blk_tensor_name(i, NAME_UP), up);
+                        self.tensors.insert(blk_weight_name(i, K::FfnGate), gate);
+                        self.tensors.insert(blk_weight_name(i, K::FfnUp), up);
                     }
                     _ => {
                         self.tensors.insert(name, tensor);
@@ -68,16 +69,26 @@ impl Content<'_> {
 
 const NUM_QKV: usize = 3;
 const NUM_GATE_UP: usize = 2;
-const NAME_QKV: &str = "attn_qkv";
-const NAME_Q: &str = "attn_q";
-const NAME_K: &str = "attn_k";
-const NAME_V: &str = "attn_v";
-const NAME_GATE_UP: &str = "ffn_gate_up";
-const NAME_GATE: &str = "ffn_gate";
-const NAME_UP: &str = "ffn_up";
+
+/// 解析块中张量的权重名字，返回块序号和张量种类
+fn blk_weight(name: &str) -> Option<(usize, K)> {
+    match name.parse() {
+        Ok(GGufTensorName {
+            block: Some(i),
+            kind,
+            suffix: Some(GGufTensorSuffix::Weight),
+        }) => Some((i, kind)),
+        _ => None,
+    }
+}
+
+#[inline]
+fn blk_weight_name(i: usize, kind: K) -> Cow<'static, str> {
+    GGufTensorName::blk_weight(i, kind).to_string().into()
+}
 
 struct MergeCollector<'a, const N: usize> {
-    buf: Vec<[Option<Tensor<'a>>; N]>,
+    buf: Vec<[Option<GGufTensorBuf<'a>>; N]>,
 }
 
 impl<'a, const N: usize> MergeCollector<'a, N> {
@@ -87,8 +98,12 @@ impl<'a, const N: usize> MergeCollector<'a, N> {
         }
     }
 
-    fn collect(&mut self, i: &str, tensor: Tensor<'a>, k: usize) -> Option<[Tensor<'a>; N]> {
-        let i: usize = i.parse().unwrap();
+    fn collect(
+        &mut self,
+        i: usize,
+        tensor: GGufTensorBuf<'a>,
+        k: usize,
+    ) -> Option<[GGufTensorBuf<'a>; N]> {
         self.buf[i][k] = Some(tensor);
         if self.buf[i].iter().all(Option::is_some) {
             Some(std::array::from_fn(|k| self.buf[i][k].take().unwrap()))
@@ -99,7 +114,12 @@ impl<'a, const N: usize> MergeCollector<'a, N> {
 }
 
 impl<'a> MergeCollector<'a, NUM_QKV> {
-    fn put(&mut self, tensor: Tensor<'a>, i: &str, k: usize) -> Option<(Cow<'a, str>, Tensor<'a>)> {
+    fn put(
+        &mut self,
+        tensor: GGufTensorBuf<'a>,
+        i: usize,
+        k: usize,
+    ) -> Option<(Cow<'a, str>, GGufTensorBuf<'a>)> {
         self.collect(i, tensor, k).map(|[q, k, v]| {
             let qr = q.shape[1];
             let kr = k.shape[1];
@@ -107,34 +127,39 @@ impl<'a> MergeCollector<'a, NUM_QKV> {
             assert_eq!(qr % kr, 0);
             assert!(qr >= kr);
             assert_eq!(kr, vr);
-            (blk_tensor_name(i, NAME_QKV), concat1([q, k, v]))
+            (blk_weight_name(i, K::AttnQkv), concat1([q, k, v]))
         })
     }
 }
 
 impl<'a> MergeCollector<'a, NUM_GATE_UP> {
-    fn put(&mut self, tensor: Tensor<'a>, i: &str, k: usize) -> Option<(Cow<'a, str>, Tensor<'a>)> {
+    fn put(
+        &mut self,
+        tensor: GGufTensorBuf<'a>,
+        i: usize,
+        k: usize,
+    ) -> Option<(Cow<'a, str>, GGufTensorBuf<'a>)> {
         self.collect(i, tensor, k).map(|[gate, up]| {
             assert_eq!(gate.shape[1], up.shape[1]);
-            (blk_tensor_name(i, NAME_GATE_UP), concat1([gate, up]))
+            (blk_weight_name(i, K::FfnGateUp), concat1([gate, up]))
         })
     }
 }
 
-fn split_qkv(tensor: Tensor) -> [Tensor; NUM_QKV] {
+fn split_qkv(tensor: GGufTensorBuf) -> [GGufTensorBuf; NUM_QKV] {
     let [c, r, _] = distruct(&tensor);
     let rq = c;
     let rkv = (r - c) / 2;
     split1(tensor, [rq, rkv, rkv])
 }
 
-fn split_gate_up(tensor: Tensor) -> [Tensor; NUM_GATE_UP] {
+fn split_gate_up(tensor: GGufTensorBuf) -> [GGufTensorBuf; NUM_GATE_UP] {
     let r = tensor.shape[1] / 2;
     split1(tensor, [r, r])
 }
 
 /// 解构形状，补充分布维度
-fn distruct(t: &Tensor) -> [u64; 3] {
+fn distruct(t: &GGufTensorBuf) -> [u64; 3] {
     match *t.shape {
         [c, r] => [c, r, 1],
         [c, r, n] => [c, r, n],
@@ -158,7 +183,7 @@ macro_rules! split0 {
     };
 }
 
-fn concat1<const N: usize>(tensors: [Tensor; N]) -> Tensor {
+fn concat1<const N: usize>(tensors: [GGufTensorBuf; N]) -> GGufTensorBuf {
     // 提取数据类型和形状
     let ty = tensors[0].ty;
     let [c, mut r, n] = distruct(&tensors[0]);
@@ -172,10 +197,10 @@ fn concat1<const N: usize>(tensors: [Tensor; N]) -> Tensor {
     let r = r;
     let data = tensors.map(|t| t.data);
     // 生成张量
-    Tensor {
+    GGufTensorBuf {
         ty,
         shape: construct(c, r, n),
-        data: DataPromise::lazy(move || {
+        data: GGufTensorData::lazy(move || {
             let data: [_; N] = std::array::from_fn(|i| data[i].get());
 
             let len = data.iter().map(|s| s.len()).sum();
@@ -198,7 +223,7 @@ fn concat1<const N: usize>(tensors: [Tensor; N]) -> Tensor {
     }
 }
 
-fn split1<const N: usize>(tensor: Tensor, split: [u64; N]) -> [Tensor; N] {
+fn split1<const N: usize>(tensor: GGufTensorBuf, split: [u64; N]) -> [GGufTensorBuf; N] {
     // 提取数据类型和形状
     let ty = tensor.ty;
     let [c, r, n] = distruct(&tensor);
@@ -213,10 +238,10 @@ fn split1<const N: usize>(tensor: Tensor, split: [u64; N]) -> [Tensor; N] {
         let data = tensor.data.clone();
         let presum_ = presum;
         presum += d_;
-        Tensor {
+        GGufTensorBuf {
             ty,
             shape: construct(c, r_, n),
-            data: DataPromise::lazy(move || {
+            data: GGufTensorData::lazy(move || {
                 let n = n as _;
                 let data = data.get();
                 assert_eq!(data.len(), d * n);
@@ -4,14 +4,12 @@ mod set_meta;
 mod sort;
 mod to_llama;
 
-use super::{compile_patterns, Content, DataPromise};
+use super::{compile_patterns, Content};
 use ggus::{GGmlType, GGufMetaDataValueType, GGufMetaMapExt};
 use regex::Regex;
 use std::{
-    borrow::Cow,
     collections::HashMap,
     fmt::{self},
-    sync::LazyLock,
 };
 
 #[allow(unused)]
@@ -23,6 +21,7 @@ pub(crate) enum Operator {
     MergeLinear(bool),
     SetMeta(HashMap<String, (GGufMetaDataValueType, Vec<u8>)>),
     SortTensors,
+    Endian(bool),
 }
 
 impl fmt::Display for Operator {
@@ -43,6 +42,10 @@ impl fmt::Display for Operator {
                 write!(f, "set-meta: {} items", map.len())
             }
             Self::SortTensors => write!(f, "sort-tensors"),
+            &Self::Endian(native) => {
+                let little = cfg!(target_endian = "little") == native;
+                write!(f, "endian: {}", if little { "little" } else { "big" })
+            }
         }
     }
 }
@@ -57,6 +60,16 @@ impl Operator {
     pub fn filter_tensor_name(p: impl AsRef<str>) -> Self {
         Self::FilterTensorName(compile_patterns(p.as_ref()))
     }
+
+    #[inline]
+    pub fn endian(endian: &str) -> Self {
+        Self::Endian(match endian.trim().to_lowercase().as_str() {
+            "native" => true,
+            "little" | "le" => cfg!(target_endian = "little"),
+            "big" | "be" => cfg!(target_endian = "big"),
+            _ => panic!("Unsupported endian: {endian}"),
+        })
+    }
 }
 
 impl Content<'_> {
@@ -70,6 +83,7 @@ impl Content<'_> {
             MergeLinear(ty) => self.merge_linear(ty),
             SetMeta(map) => self.set_meta(map),
             SortTensors => self.sort_tensors(),
+            Endian(native) => self.native_endian = native,
         }
     }
 
@@ -80,11 +94,3 @@ impl Content<'_> {
         }
     }
 }
-
-static BLK_TENSOR_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^blk\.(\d+)\.(\w+)\.weight$").unwrap());
-
-#[inline]
-fn blk_tensor_name(i: impl fmt::Display, name: impl fmt::Display) -> Cow<'static, str> {
-    format!("blk.{i}.{name}.weight").into()
-}
@@ -1,5 +1,5 @@
-﻿use super::{super::MetaValue, Content, Operator};
-use ggus::{GGufMetaDataValueType as Ty, GGufWriter, GENERAL_ALIGNMENT};
+﻿use super::{Content, Operator};
+use ggus::{GGufMetaBuf, GGufMetaDataValueType as Ty, GGufWriter, GENERAL_ALIGNMENT};
 use internal::StrCollector;
 use log::warn;
 use regex::Regex;
@@ -45,7 +45,7 @@ impl Content<'_> {
             } else {
                 self.meta_kvs.insert(
                     k.into(),
-                    MetaValue {
+                    GGufMetaBuf {
                         ty,
                         value: vec.into(),
                     },
@@ -1,10 +1,8 @@
-﻿use super::{
-    super::{DataPromise, MetaValue, Tensor},
-    Content, BLK_TENSOR_REGEX,
-};
+﻿use super::Content;
 use ggus::{
     ggml_quants::{bf16, f16},
-    DataFuture, GGmlType, GGufMetaMapExt,
+    DataFuture, GGmlType, GGufMetaBuf, GGufMetaMapExt, GGufTensorBuf, GGufTensorData,
+    GGufTensorKind as K, GGufTensorName, GGufTensorSuffix,
 };
 use memmap2::MmapMut;
 use std::{alloc::Layout, ops::MulAssign};
@@ -39,13 +37,18 @@ fn from_minicpm(content: &mut Content, extra: Option<String>) {
     let res_scale = res_scale.expect(ERR_MSG) / (nblk as f64).sqrt();
 
     for (name, tensor) in content.tensors.iter_mut() {
-        if name == "token_embd.weight" {
-            scale_tensor(tensor, embd_scale);
-        } else if let Some(captures) = BLK_TENSOR_REGEX.captures(name) {
-            match &captures[2] {
-                "attn_output" | "ffn_down" => scale_tensor(tensor, res_scale),
-                _ => {}
-            }
+        let Ok(GGufTensorName {
+            kind,
+            suffix: Some(GGufTensorSuffix::Weight),
+            ..
+        }) = name.parse()
+        else {
+            continue;
+        };
+        match kind {
+            K::TokenEmbd => scale_tensor(tensor, embd_scale),
+            K::AttnOutput | K::FfnDown => scale_tensor(tensor, res_scale),
+            _ => {}
         }
     }
 
@@ -56,7 +59,7 @@ fn set_arch(content: &mut Content, old: &str, new: &str) {
     let old = format!("{old}.");
     for (k, v) in std::mem::take(&mut content.meta_kvs) {
         if k == "general.architecture" {
-            content.meta_kvs.insert(k, MetaValue::string(new));
+            content.meta_kvs.insert(k, GGufMetaBuf::string(new));
         } else {
             let k = match k.strip_prefix(&old) {
                 Some(body) => format!("{new}.{body}").into(),
@@ -67,13 +70,15 @@ fn set_arch(content: &mut Content, old: &str, new: &str) {
     }
 }
 
-fn scale_tensor(tensor: &mut Tensor, scale: f64) {
+fn scale_tensor(tensor: &mut GGufTensorBuf, scale: f64) {
     let data = tensor.data.clone();
     tensor.data = match tensor.ty {
-        GGmlType::F64 => DataPromise::lazy(move || scale_data(data.get(), scale)),
-        GGmlType::F32 => DataPromise::lazy(move || scale_data(data.get(), scale as f32)),
-        GGmlType::F16 => DataPromise::lazy(move || scale_data(data.get(), f16::from_f64(scale))),
-        GGmlType::BF16 => DataPromise::lazy(move || scale_data(data.get(), bf16::from_f64(scale))),
+        GGmlType::F64 => GGufTensorData::lazy(move || scale_data(data.get(), scale)),
+        GGmlType::F32 => GGufTensorData::lazy(move || scale_data(data.get(), scale as f32)),
+        GGmlType::F16 => GGufTensorData::lazy(move || scale_data(data.get(), f16::from_f64(scale))),
+        GGmlType::BF16 => {
+            GGufTensorData::lazy(move || scale_data(data.get(), bf16::from_f64(scale)))
+        }
         ty => todo!("unsupported tensor type: {ty:?}"),
     };
 }
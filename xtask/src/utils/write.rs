@@ -1,14 +1,16 @@
 ﻿use super::{Content, FileInfo, OutputConfig};
-use ggus::{GGufFileHeader, GGufFileSimulator, GGufFileWriter};
-use std::{fs::File, io, iter::zip, path::PathBuf, thread};
+use ggus::{
+    GGufDocument, GGufFileSimulator, GGufMetaDataValueType as Ty, SPLIT_COUNT, SPLIT_NO,
+    SPLIT_TENSORS_COUNT,
+};
+use std::{fs::File, io, iter::zip, ops::Range, path::PathBuf, thread};
 
 impl Content<'_> {
     pub fn write_files(self, out: OutputConfig) -> Result<Vec<FileInfo>, io::Error> {
         let Self {
             name,
-            alignment,
-            meta_kvs,
-            tensors,
+            native_endian,
+            doc,
         } = self;
         let OutputConfig {
             dir,
@@ -16,45 +18,67 @@ impl Content<'_> {
             shard_max_file_size,
             shard_no_tensor_first,
         } = out;
+        let GGufDocument {
+            alignment,
+            meta_kvs,
+            tensors,
+        } = &doc;
+        let alignment = *alignment;
 
-        // 规划分片方案
+        // 规划分片方案，记录每个分片的张量范围
 
-        let mut simulator = GGufFileSimulator::with_alignment(alignment);
-        for (k, v) in &meta_kvs {
-            simulator.write_meta_kv(k, v.ty, &v.value);
-        }
+        // 每个分片都带有 split.* 元信息，按分片写入估计大小；只有一个分片时实际文件会略小
+        let new_shard = |first: bool| {
+            let mut simulator = GGufFileSimulator::with_alignment(alignment);
+            simulator.write_meta_kv(SPLIT_NO, Ty::U16, &0u16.to_ne_bytes());
+            simulator.write_meta_kv(SPLIT_COUNT, Ty::U16, &0u16.to_ne_bytes());
+            simulator.write_meta_kv(SPLIT_TENSORS_COUNT, Ty::I32, &0i32.to_ne_bytes());
+            if first {
+                for (k, v) in meta_kvs {
+                    simulator.write_meta_kv(k, v.ty, &v.value);
+                }
+            }
+            simulator.finish()
+        };
 
-        let mut simulator = simulator.finish();
-        let mut shards = vec![vec![]];
-        for (name, tensor) in tensors {
+        let mut simulator = new_shard(true);
+        let mut shards = vec![Range::default()];
+        for (i, (name, tensor)) in tensors.iter().enumerate() {
             match &mut *shards {
                 [_] if shard_no_tensor_first => {
-                    simulator = GGufFileSimulator::with_alignment(alignment).finish();
-                    simulator.write_tensor(&name, tensor.ty, &tensor.shape);
-                    shards.push(vec![(name, tensor)]);
+                    simulator = new_shard(false);
+                    simulator.write_tensor(name, tensor.ty, &tensor.shape);
+                    shards.push(i..i + 1);
                 }
                 [.., current] => {
-                    simulator.write_tensor(&name, tensor.ty, &tensor.shape);
+                    simulator.write_tensor(name, tensor.ty, &tensor.shape);
                     if current.len() < shard_max_tensor_count
                         && simulator.written_bytes() < shard_max_file_size.nbytes()
                     {
-                        current.push((name, tensor));
+                        current.end += 1;
                     } else {
-                        simulator = GGufFileSimulator::with_alignment(alignment).finish();
-                        simulator.write_tensor(&name, tensor.ty, &tensor.shape);
-                        shards.push(vec![(name, tensor)]);
+                        simulator = new_shard(false);
+                        simulator.write_tensor(name, tensor.ty, &tensor.shape);
+                        shards.push(i..i + 1);
                     }
                 }
                 [] => unreachable!(),
             }
         }
+        if u16::try_from(shards.len()).is_err() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} shards exceed the limit of {}", shards.len(), u16::MAX),
+            ));
+        }
 
         // 生成迭代器
 
-        let meta_kvs = &meta_kvs;
+        let doc = &doc;
+        let n_shards = shards.len();
         let dir = dir.unwrap_or_else(|| std::env::current_dir().unwrap());
         let path = name
-            .split_n(shards.len())
+            .split_n(n_shards)
             .map(|name| dir.join(name.to_string()));
 
         // 并行写入文件
@@ -66,24 +90,20 @@ impl Content<'_> {
                 .map(|(i, (tensors, path))| {
                     s.spawn(move || -> Result<FileInfo, io::Error> {
                         let path = find_path(path);
-
-                        let n_meta_kvs = if i == 0 { meta_kvs.len() + 1 } else { 1 };
+                        let file = File::create(&path)?;
                         let n_tensors = tensors.len();
-                        let header = GGufFileHeader::new(3, n_tensors as _, n_meta_kvs as _);
-
-                        let mut writer = GGufFileWriter::new(File::create(&path)?, header)?;
-                        writer.write_alignment(alignment)?;
-                        if i == 0 {
-                            for (k, v) in meta_kvs {
-                                writer.write_meta_kv(k, v.ty, &v.value)?;
-                            }
-                        }
-
-                        let mut writer = writer.finish();
-                        for (name, tensor) in tensors {
-                            writer.write_tensor(&name, tensor.ty, &tensor.shape, tensor.data)?;
-                        }
-                        writer.finish().map(|n_bytes| FileInfo {
+                        let (n_meta_kvs, n_bytes) = if n_shards > 1 {
+                            (
+                                doc.shard_meta_kv_count(i),
+                                doc.write_shard(file, tensors, i, n_shards, native_endian)?,
+                            )
+                        } else {
+                            (
+                                doc.meta_kv_count(),
+                                doc.write_with_endian(file, native_endian)?,
+                            )
+                        };
+                        Ok(FileInfo {
                             path,
                             n_tensors,
                             n_meta_kvs,